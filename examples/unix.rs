@@ -1,90 +1,13 @@
-// The goal of this example is to show an implementation of the library on Unix-based OSes
-// In order to use the library, one must have the TcpClient trait implemented.
-// Implementing the Trait using TcpStream (std::net) hasn't been really straightforward due
-// to the constraints brought by the Trait asking to create an unused socket, which is not possible
-// with TcpStream (check out `socket` function).
-
-use embedded_nal::{TcpClient, SocketAddr, nb};
-use std::net::{TcpStream, Shutdown};
-use std::str::{FromStr, from_utf8};
-use std::io::{Write, Read};
-use std::time::Duration;
-use leger::{Provider, ProviderError, TcpError};
+// The goal of this example is to show an implementation of the library on Unix-based OSes,
+// using the `TcpStreamTransport` provided with the `std` feature.
+
+use leger::{Provider, ProviderError};
 use leger::chain::Chain;
-use leger::account::{Account, Key, LegerSigner, PREFIX};
-use schnorrkel::{SecretKey, Keypair, Signature, signing_context, MiniSecretKey};
-use blake2_rfc::blake2b::Blake2b;
+use leger::account::{Account, Key, LegerSigner, LegerVerifier};
+use schnorrkel::{SecretKey, Keypair, PublicKey, Signature, signing_context, MiniSecretKey};
 use leger::extrinsic::ExtrinsicCalls;
-
-pub struct UnixTcpStack {
-}
-
-impl TcpClient for UnixTcpStack {
-	type TcpSocket = Option<TcpStream>;
-	type Error = TcpError;
-
-	// We want the socket to be created but we don't want any connection
-	// using TcpStream don't allow to do so, so I am calling the connect function
-	// to a default address
-	// TODO, there should be a better way to handle this!
-	fn socket(&self) -> Result<Self::TcpSocket, Self::Error> {
-		Ok(None)
-	}
-
-	fn connect(&self, socket: &mut Self::TcpSocket, remote: SocketAddr) -> nb::Result<(), Self::Error> {
-		let addrs = [ std::net::SocketAddr::from_str(remote.to_string().as_str()).unwrap() ];
-
-		let mut new_sock = TcpStream::connect(&addrs[..]).ok();
-
-		if new_sock.is_some() {
-			socket.replace(new_sock.unwrap());
-			socket.as_ref().unwrap().set_read_timeout(Some(Duration::new(2, 0)));
-			Ok(())
-		} else {
-			Err(nb::Error::Other(TcpError::CannotConnect))
-		}
-	}
-
-	fn is_connected(&self, socket: &Self::TcpSocket) -> Result<bool, Self::Error> {
-		Ok(socket.is_some())
-	}
-
-	fn send(&self, socket: &mut Self::TcpSocket, buffer: &[u8]) -> nb::Result<usize, Self::Error> {
-		if socket.is_none() {
-			return Err(nb::Error::Other(TcpError::CannotConnect))
-		}
-
-		if let Ok(written) = socket.as_ref().unwrap().write(buffer) {
-			Ok(written)
-		} else {
-			Err(nb::Error::WouldBlock)
-		}
-	}
-
-	fn receive(&self, socket: &mut Self::TcpSocket, buffer: &mut [u8]) -> nb::Result<usize, Self::Error> {
-		if socket.is_none() {
-			return Err(nb::Error::Other(TcpError::CannotConnect))
-		}
-
-		if let Ok(read) = socket.as_ref().unwrap().read(buffer) {
-			Ok(read)
-		} else {
-			Err(nb::Error::WouldBlock)
-		}
-	}
-
-	fn close(&self, socket: Self::TcpSocket) -> Result<(), Self::Error> {
-		if socket.is_none() {
-			return Ok(())
-		}
-
-		if let Ok(_) = socket.unwrap().shutdown(Shutdown::Both) {
-			Ok(())
-		} else {
-			Err(TcpError::CannotClose)
-		}
-	}
-}
+use leger::rpc::{RpcBuffers, Endpoint};
+use leger::transport::TcpStreamTransport;
 
 pub struct LocalSigner {
 	keys: Keypair,
@@ -117,33 +40,24 @@ impl LegerSigner for LocalSigner {
 	}
 }
 
-pub trait KeyFormat {
-	fn to_ss58(&self) -> String;
-}
+impl LegerVerifier for LocalSigner {
+	fn verify_sr25519(&self, public: &Key, payload: &[u8], signature: &[u8; 64]) -> bool {
+		let public = PublicKey::from_bytes(public.as_ref());
+		let sig = Signature::from_bytes(signature.as_ref());
+
+		match (public, sig) {
+			(Ok(p), Ok(s)) => p.verify_simple(b"substrate", payload, &s).is_ok(),
+			_ => false
+		}
+	}
 
-impl KeyFormat for Key {
-	fn to_ss58(&self) -> String {
-		let mut body = [0_u8; 35];
-		let mut output = [0_u8; 64];
-
-		// concatenate address type and public key
-		// address-Type is Generic Substrate wildcard
-		body[0] = 0x2A;
-		body[1..].iter_mut()
-			.zip(self.iter())
-			.for_each(|(f, t)| *f = *t);
-
-		let mut hasher = Blake2b::new(64);
-		hasher.update(PREFIX);
-		hasher.update(body[0..33].as_ref());
-		let hash = hasher.finalize();
-
-		body[33..].iter_mut().zip(hash.as_ref().iter())
-			.for_each(|(f, t)| *f = *t);
-
-		bs58::encode(body.as_ref()).into(&mut output[..]).unwrap();
-		let s = from_utf8(output.as_ref()).unwrap();
-		s.to_string()
+	// Only Sr25519 is supported in this example
+	fn verify_ed25519(&self, _public: &Key, _payload: &[u8], _signature: &[u8; 64]) -> bool {
+		false
+	}
+
+	fn recover_ecdsa(&self, _message_hash: &[u8; 32], _signature: &[u8; 65]) -> Option<[u8; 33]> {
+		None
 	}
 }
 
@@ -156,8 +70,16 @@ fn main() -> Result<(), ProviderError> {
 		"e5be9a5092b81bca64be81d212e7f2f9eba183bb7a90954f7b76361f6edb5c0a",
 		&mut seed as &mut [u8])
 		.expect("Cannot decode hex string");
-	let tcp = UnixTcpStack{	};
-	let mut pp: Provider<Option<TcpStream>> = Provider::new(&tcp, "127.0.0.1:9944")?;
+	let mut in_buf = [0_u8; 4096];
+	let mut out_buf = [0_u8; 4096];
+	let mut req_buf = [0_u8; 1024];
+	let buffers = RpcBuffers {
+		in_buf: &mut in_buf,
+		out_buf: &mut out_buf,
+		req_buf: &mut req_buf,
+	};
+	let endpoint = Endpoint::parse("ws://127.0.0.1:9944")?;
+	let mut pp = Provider::new(TcpStreamTransport::new(), endpoint, buffers)?;
 
 	let signer = LocalSigner::new(seed);
 	let mut account = Account::new(&signer);
@@ -170,7 +92,11 @@ fn main() -> Result<(), ProviderError> {
 	let genesis = pp.system_version()?;
 	println!("✌️ Version {}", genesis);
 
-	println!("🐥 Runtime version {}", pp.runtime_version()?);
+	let runtime = pp.runtime_version()?;
+	println!("🐥 Runtime {} version {}", runtime.spec_name, runtime.spec_version);
+
+	let health = pp.system_health()?;
+	println!("🩺 {} peers, syncing: {}", health.peers, health.is_syncing);
 
 	let resp = pp.get_genesis_block_hash()?;
 	println!("🐥 Genesis block hash 0x{:02x?}", resp);
@@ -178,10 +104,15 @@ fn main() -> Result<(), ProviderError> {
 	let resp = pp.get_block_hash(None)?;
 	println!("🏷 Last block hash 0x{:02x?}",resp);
 
-	let resp = pp.get_finalized_head()?;
-	println!("🤖 Finalized head {}", resp);
+	let finalized = pp.get_finalized_head()?;
+	println!("🤖 Finalized head 0x{:02x?}", finalized);
+
+	let header = pp.get_header(Some(&finalized))?;
+	let best = pp.get_block_number()?;
+	println!("🧱 Finalized block #{}, {} blocks behind #{}", header.number, best.saturating_sub(header.number), best);
 
-	println!("🔑 Using account: {}", account.u8a().to_ss58());
+	println!("🔑 Using account: {}", account.ss58());
+	println!("🔢 Next index: {}", pp.account_next_index(&account)?);
 
 	let resp = account.get_info(&mut pp);
 	if let Ok(r) = resp {
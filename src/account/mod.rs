@@ -1,26 +1,30 @@
 use crate::Provider;
+use crate::transport::Transport;
+use crate::storage::StorageKey;
+use crate::hashing::{StorageHasher, blake2_256};
 use core::{str, mem};
 use heapless::{String, Vec, consts::*};
 use blake2_rfc::blake2b::Blake2b;
-use core::convert::TryInto;
-use core::convert::TryFrom;
+
+#[cfg(test)]
+mod tests;
 
 #[derive(Debug)]
 pub enum AccountError {
 	CannotFetchAccountInfo,
 	CannotConvert,
+	InvalidSize,
 }
 
-#[repr(C)]
-#[derive(Debug, Clone, Copy)]
+/// Account info stored on chain, zeroed for accounts which don't exist yet
+#[derive(Debug, Clone, Copy, Default)]
 pub struct AccountInfo {
 	nonce: u32,
 	ref_count: u32,
 	data: AccountData
 }
 
-#[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct AccountData {
 	free: u128,
 	reserved: u128,
@@ -28,6 +32,23 @@ pub struct AccountData {
 	free_frozen: u128,
 }
 
+impl AccountInfo {
+	/// Number of transactions sent by the account
+	pub fn nonce(&self) -> u32 {
+		self.nonce
+	}
+
+	/// Free balance, frozen part included
+	pub fn free(&self) -> u128 {
+		self.data.free
+	}
+
+	/// Reserved balance, e.g. deposits
+	pub fn reserved(&self) -> u128 {
+		self.data.reserved
+	}
+}
+
 pub struct Account<'a> {
 	public: Key,
 	signer: &'a dyn LegerSigner,
@@ -41,11 +62,133 @@ pub trait LegerSigner {
 	fn sign(&self, payload: &[u8], signature: &mut [u8; 64]);
 }
 
+/// This trait must be implemented to verify signatures received from a remote party.
+/// As for [`LegerSigner`](trait.LegerSigner.html), the crypto can be performed by a secure element or a library.
+pub trait LegerVerifier {
+	fn verify_sr25519(&self, public: &Key, payload: &[u8], signature: &[u8; 64]) -> bool;
+	fn verify_ed25519(&self, public: &Key, payload: &[u8], signature: &[u8; 64]) -> bool;
+	/// Recovers the compressed public key (33 bytes) used to sign `message_hash`.
+	/// `message_hash` is the Blake2b (32 bytes) of the signed payload.
+	fn recover_ecdsa(&self, message_hash: &[u8; 32], signature: &[u8; 65]) -> Option<[u8; 33]>;
+}
+
+/// Size of the `System.Account` storage key: prefixes and `Blake2_128Concat` account ID
+const STORAGE_KEY_SIZE: usize = 80;
+/// Size of the storage key encoded as an hex string: "0x" + `STORAGE_KEY_SIZE` bytes
+pub(crate) const STORAGE_KEY_HEX_SIZE: usize = 2 + STORAGE_KEY_SIZE * 2;
+
 /// Key type is an array of 32 bytes
 pub type Key = [u8; 32];
 
 pub const PREFIX: &[u8] = b"SS58PRE";
 
+/// Messages are wrapped into `<Bytes>...</Bytes>` before being signed, as done by polkadot-js `signRaw`
+const WRAP_PREFIX: &[u8] = b"<Bytes>";
+const WRAP_SUFFIX: &[u8] = b"</Bytes>";
+
+/// Maximum size of a message to be signed, wrapping included
+pub const MAXIMUM_MESSAGE_SIZE_BYTES: usize = 256;
+
+/// Signature along with the scheme used to compute it, same as Substrate's `MultiSignature`
+#[derive(Debug, Clone, Copy)]
+pub enum MultiSignature {
+	Ed25519([u8; 64]),
+	Sr25519([u8; 64]),
+	Ecdsa([u8; 65]),
+}
+
+impl MultiSignature {
+	/// SCALE encodes the signature into `payload` (scheme index followed by the signature bytes)
+	/// and returns the number of bytes written
+	pub fn encode(&self, payload: &mut [u8]) -> usize {
+		let (index, signature): (u8, &[u8]) = match self {
+			MultiSignature::Ed25519(sig) => (0x00, sig.as_ref()),
+			MultiSignature::Sr25519(sig) => (0x01, sig.as_ref()),
+			MultiSignature::Ecdsa(sig) => (0x02, sig.as_ref()),
+		};
+
+		payload[0] = index;
+		payload[1..1+signature.len()].copy_from_slice(signature);
+		1 + signature.len()
+	}
+
+	/// Size of the SCALE encoded signature
+	pub fn encoded_size(&self) -> usize {
+		match self {
+			MultiSignature::Ed25519(_) | MultiSignature::Sr25519(_) => 1 + 64,
+			MultiSignature::Ecdsa(_) => 1 + 65,
+		}
+	}
+
+	/// Verifies the signature of `payload` against the `signer` key.
+	///
+	/// ECDSA public keys don't fit into a `Key`, so as in Substrate, `signer` is expected to be
+	/// the Blake2b (32 bytes) of the compressed public key recovered from the signature.
+	pub fn verify(&self, verifier: &dyn LegerVerifier, payload: &[u8], signer: &Key) -> bool {
+		match self {
+			MultiSignature::Ed25519(sig) => verifier.verify_ed25519(signer, payload, sig),
+			MultiSignature::Sr25519(sig) => verifier.verify_sr25519(signer, payload, sig),
+			MultiSignature::Ecdsa(sig) => {
+				if let Some(public) = verifier.recover_ecdsa(&blake2_256(payload), sig) {
+					blake2_256(public.as_ref()) == *signer
+				} else {
+					false
+				}
+			}
+		}
+	}
+}
+
+/// Size of the SCALE-encoded account info: nonce and reference count, then the four balances.
+/// It differs from the size of `AccountInfo` on targets aligning `u128` on 16 bytes.
+const ACCOUNT_INFO_SIZE: usize = 2 * mem::size_of::<u32>() + 4 * mem::size_of::<u128>();
+
+/// Decodes the account info returned by `state_getStorage`.
+/// Storage is empty (`None`) for accounts which don't exist yet, e.g. never funded: their info
+/// is zeroed, with a zero balance.
+///
+/// ## Errors
+/// * InvalidSize: the value is not exactly `ACCOUNT_INFO_SIZE` bytes long
+/// * CannotConvert: the value is not an hex string
+fn decode_info(res: Option<&str>) -> Result<AccountInfo, AccountError> {
+	let res = match res {
+		Some(res) => res,
+		None => return Ok(AccountInfo::default()),
+	};
+
+	// AccountInfo is SCALE-encoded into an hex string starting with "0x"
+	let hex_data = res.strip_prefix("0x").unwrap_or(res);
+	if hex_data.len() != ACCOUNT_INFO_SIZE * 2 {
+		return Err(AccountError::InvalidSize)
+	}
+	let mut data = [0_u8; ACCOUNT_INFO_SIZE];
+	hex::decode_to_slice(hex_data, &mut data as &mut [u8]).map_err(|_| AccountError::CannotConvert)?;
+
+	// integers are little endian
+	Ok(AccountInfo {
+		nonce: read_u32(&data[0..]),
+		ref_count: read_u32(&data[4..]),
+		data: AccountData {
+			free: read_u128(&data[8..]),
+			reserved: read_u128(&data[24..]),
+			misc_frozen: read_u128(&data[40..]),
+			free_frozen: read_u128(&data[56..]),
+		},
+	})
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+	let mut bytes = [0_u8; 4];
+	bytes.copy_from_slice(&data[..4]);
+	u32::from_le_bytes(bytes)
+}
+
+fn read_u128(data: &[u8]) -> u128 {
+	let mut bytes = [0_u8; 16];
+	bytes.copy_from_slice(&data[..16]);
+	u128::from_le_bytes(bytes)
+}
+
 impl<'a> Account<'a> {
 	/// Creates an account from private key (secret seed)
 	/// Creating account from secret phrase is not supported yet.
@@ -62,98 +205,144 @@ impl<'a> Account<'a> {
 		self.signer.sign(msg, signature);
 	}
 
+	/// Signs an arbitrary message, compatible with polkadot-js `signRaw`:
+	/// the message is wrapped into `<Bytes>...</Bytes>` (unless already wrapped) before being signed.
+	/// Returns the signature as an hex string starting with "0x".
+	///
+	/// This is not intended to sign extrinsics, see [`ExtrinsicPayload`](../extrinsic/struct.ExtrinsicPayload.html).
+	///
+	/// ## Errors
+	/// * InvalidSize: wrapped message is larger than `MAXIMUM_MESSAGE_SIZE_BYTES`
+	/// * CannotConvert: signature cannot be encoded as hex string
+	pub fn sign_message(&self, message: &[u8]) -> Result<String<U130>, AccountError> {
+		let (prefix, suffix): (&[u8], &[u8]) =
+			if message.starts_with(WRAP_PREFIX) && message.ends_with(WRAP_SUFFIX) {
+				(&[], &[])
+			} else {
+				(WRAP_PREFIX, WRAP_SUFFIX)
+			};
+
+		let mut wrapped = [0_u8; MAXIMUM_MESSAGE_SIZE_BYTES];
+		let len = prefix.len() + message.len() + suffix.len();
+		if len > wrapped.len() {
+			return Err(AccountError::InvalidSize)
+		}
+
+		wrapped[..prefix.len()].copy_from_slice(prefix);
+		wrapped[prefix.len()..prefix.len()+message.len()].copy_from_slice(message);
+		wrapped[prefix.len()+message.len()..len].copy_from_slice(suffix);
+
+		let mut signature = [0_u8; 64];
+		self.signer.sign(wrapped[..len].as_ref(), &mut signature);
+
+		// "0x" + signature encoded as hex string
+		let mut signature_hex = [0_u8; 130];
+		signature_hex[0] = 0x30; // "0"
+		signature_hex[1] = 0x78; // "x"
+		if hex::encode_to_slice::<[u8; 64]>(signature, &mut signature_hex[2..]).is_err() {
+			return Err(AccountError::CannotConvert)
+		}
+
+		let mut res = String::new();
+		str::from_utf8(signature_hex.as_ref())
+			.map_err(|_| AccountError::CannotConvert)
+			.and_then(|s| res.push_str(s).map_err(|_| AccountError::InvalidSize))?;
+
+		Ok(res)
+	}
+
 	/// Get public key array
 	pub fn u8a(&self) -> Key {
 		self.public
 	}
 
-	/// Get account info from node storage.
-	/// If the provider is not able to fetch data, the last known data is used.
+	/// SS58 address of the account, with the generic Substrate address type (42)
+	pub fn ss58(&self) -> String<U48> {
+		const ADDRESS_TYPE: u8 = 42;
+		let mut body = [0_u8; 35];
+		let mut output = [0_u8; 48];
+
+		body[0] = ADDRESS_TYPE;
+		body[1..33].copy_from_slice(&self.public);
+
+		// checksum: first 2 bytes of the Blake2b-512 of the prefixed body
+		let mut hasher = Blake2b::new(64);
+		hasher.update(PREFIX);
+		hasher.update(&body[..33]);
+		body[33..].copy_from_slice(&hasher.finalize().as_bytes()[..2]);
+
+		let mut address = String::new();
+		if let Ok(len) = bs58::encode(body.as_ref()).into(&mut output[..]) {
+			let _ = address.push_str(str::from_utf8(&output[..len]).unwrap_or(""));
+		}
+
+		address
+	}
+
+	/// Key of the account info in storage: entry of the `System.Account` map, keyed by account ID
+	pub fn storage_key(&self) -> StorageKey {
+		let mut key = StorageKey::new("System", "Account");
+		// 80 bytes, always fit
+		let _ = key.push_key(StorageHasher::Blake2_128Concat, &self.public);
+		key
+	}
+
+	/// Decodes the account info returned by `state_getStorage` and keeps it as the last known info.
 	///
 	/// ## Errors
 	/// * CannotConvert: there has been an error converting between: slice <-> hex str
-	/// * CannotFetchAccountInfo: error connecting to the provider
-	pub fn get_info<S>(&mut self, provider: &mut Provider<S>) -> Result<AccountInfo, AccountError> {
-		// The request is a concatenation as hex string of:
-		//  - key (System, Account) xxhashes
-		//  - Account ID blake2b (16 bytes)
-		//	- Account ID (transparent)
-		const REQ_SIZE: usize = 80;
-		const REQ_SIZE_STR_ENCODED: usize = 2+REQ_SIZE*2; // "0x" + string encoded REQ_SIZE buffer
-		const REQ_KEY_HASH_SIZE: usize = 32;
-		const REQ_ACCOUNT_B2B_SIZE: usize = 16;
-		let mut params = [0_u8; REQ_SIZE_STR_ENCODED];
-		params[0] = 0x30; // "0"
-		params[1] = 0x78; // "x"
-
-		// In the following block, params[2..] is filled with keys and account ID, encoded as hex string
-		{
-			// params_to_encode is a subarray of params
-			let params_to_encode: &mut[u8; REQ_SIZE] = <&mut [u8; REQ_SIZE]>::try_from(&mut params[2..2+REQ_SIZE]).unwrap();
-
-			// "System".xxHash128 = "26AA394EEA5630E07C48AE0C9558CEF7"
-			// "Account".xxHash128 = "B99D880EC681799C0CF30E8886371DA9"
-			let hashed_request: [u8; REQ_KEY_HASH_SIZE] = [0x26, 0xAA, 0x39, 0x4E, 0xEA, 0x56, 0x30, 0xE0, 0x7C, 0x48, 0xAE, 0x0C, 0x95, 0x58, 0xCE, 0xF7, 0xB9, 0x9D, 0x88, 0x0E, 0xC6, 0x81, 0x79, 0x9C, 0x0C, 0xF3, 0x0E, 0x88, 0x86, 0x37, 0x1D, 0xA9];
-			params_to_encode[..REQ_KEY_HASH_SIZE].copy_from_slice(hashed_request.as_ref());
-
-			// We need to hash the public key into Blake2b (16 bytes)
-			let u8a = self.u8a();
-
-			// TODO should we store the hash in Account so that we don't need to compute each time?
-			let mut hasher = Blake2b::new(REQ_ACCOUNT_B2B_SIZE);
-			hasher.update(u8a.as_ref());
-			let hash = hasher.finalize();
-
-			// Copy hash and account ID
-			params_to_encode[REQ_KEY_HASH_SIZE..REQ_KEY_HASH_SIZE+REQ_ACCOUNT_B2B_SIZE].copy_from_slice(hash.as_ref());
-			params_to_encode[REQ_KEY_HASH_SIZE+REQ_ACCOUNT_B2B_SIZE..].copy_from_slice(u8a.as_ref());
-
-			// `params_to_encode` has been filled
-			// we can encode as hex string and copy into params (overwriting params_to_encode)
-			hex::encode_to_slice::<[u8; REQ_SIZE]>(*params_to_encode, &mut params[2..REQ_SIZE_STR_ENCODED]).unwrap();
-		}
+	/// * InvalidSize: the value in storage is not an account info
+	pub(crate) fn update_info(&mut self, res: Option<&str>) -> Result<AccountInfo, AccountError> {
+		let acc = decode_info(res)?;
 
-		let s = core::str::from_utf8(params.as_ref()).expect("Cannot convert payload");
+		// replace last known account info
+		self.info.replace(acc);
 
-		// Sending the RPC request
-		let rpc_response = provider.rpc.rpc_method(Some("state_getStorage"), Some([s]));
-
-		// AccountInfo is packed into an hex string starting with "0x".
-		// Let's parse it if we have an answer
-		// otherwise, use last known AccountInfo
-		if let Ok(r) = rpc_response {
-			let hex_data = r.strip_prefix("0x").map_or(
-				r,
-				|v| v
-			);
+		Ok(acc)
+	}
 
-			// Now that we have removed 0x, we can parse the hex string into a slice
-			// so we can unpack into AccountInfo
-			if hex::decode_to_slice(hex_data, &mut params[..hex_data.len()/2]).is_ok() {
-				let acc;
-				unsafe { acc = mem::transmute::<[u8; 72], AccountInfo>(params[0..72].try_into().expect("Cannot convert slice to array")); }
+	/// Nonce of the last known account info, `0` if it has never been fetched
+	pub(crate) fn last_nonce(&self) -> u32 {
+		self.info.map_or(0, |i| i.nonce)
+	}
 
-				// replace last known account info
-				self.info.replace(acc);
+	/// Get account info from node storage, zeroed if the account doesn't exist.
+	/// If the provider is not able to fetch data, the last known data is used.
+	///
+	/// ## Errors
+	/// * CannotConvert: there has been an error converting between: slice <-> hex str
+	/// * InvalidSize: the value in storage is not an account info
+	/// * CannotFetchAccountInfo: error connecting to the provider
+	pub fn get_info<T: Transport>(&mut self, provider: &mut Provider<T>) -> Result<AccountInfo, AccountError> {
+		// Sending the RPC request, use last known AccountInfo if there is no answer
+		match provider.get_storage(&self.storage_key(), None) {
+			Ok(r) => self.update_info(r),
+			Err(_) => self.info.ok_or(AccountError::CannotFetchAccountInfo),
+		}
+	}
 
-				return Ok(acc)
-			} else {
-				return Err(AccountError::CannotConvert)
-			}
-		} else if let Some(i) = &self.info {
-			Ok(*i)
-		} else {
-			Err(AccountError::CannotFetchAccountInfo)
+	/// Get account info from node storage at block `at`, e.g. a finalized block, zeroed if the
+	/// account didn't exist. The last known data is not updated.
+	///
+	/// ## Errors
+	/// * CannotConvert: there has been an error converting between: slice <-> hex str
+	/// * InvalidSize: the value in storage is not an account info
+	/// * CannotFetchAccountInfo: error connecting to the provider, or state of the block has been pruned
+	pub fn get_info_at<T: Transport>(&self, provider: &mut Provider<T>, at: &[u8; 32]) -> Result<AccountInfo, AccountError> {
+		match provider.get_storage(&self.storage_key(), Some(at)) {
+			Ok(r) => decode_info(r),
+			Err(_) => Err(AccountError::CannotFetchAccountInfo),
 		}
 	}
 
-	/// Get account balance.
+	/// Get account balance, `0` if the account doesn't exist.
 	/// If the provider is not able to fetch data, the last known data is used.
 	///
 	/// ## Errors
 	/// * CannotConvert: there has been an error converting between: slice <-> hex str
+	/// * InvalidSize: the value in storage is not an account info
 	/// * CannotFetchAccountInfo: error connecting to the provider
-	pub fn get_balance<S>(&mut self, provider: &mut Provider<S>) -> Result<u128, AccountError> {
+	pub fn get_balance<T: Transport>(&mut self, provider: &mut Provider<T>) -> Result<u128, AccountError> {
 		let info = self.get_info(provider)?;
 		Ok(info.data.free)
 	}
@@ -163,8 +352,9 @@ impl<'a> Account<'a> {
 	///
 	/// ## Errors
 	/// * CannotConvert: there has been an error converting between: slice <-> hex str
+	/// * InvalidSize: the value in storage is not an account info
 	/// * CannotFetchAccountInfo: error connecting to the provider
-	pub fn get_nonce<S>(&mut self, provider: &mut Provider<S>) -> Result<u32, AccountError> {
+	pub fn get_nonce<T: Transport>(&mut self, provider: &mut Provider<T>) -> Result<u32, AccountError> {
 		let info = self.get_info(provider)?;
 		Ok(info.nonce)
 	}
@@ -0,0 +1,149 @@
+extern crate std;
+
+use std::format;
+use crate::account::{Account, AccountError, Key, LegerSigner, LegerVerifier, MultiSignature, decode_info};
+use crate::rpc::Endpoint;
+use crate::rpc::mock::{MockState, MockTransport, http_response, buffers};
+use crate::storage::MAX_STORAGE_KEY_HEX_SIZE;
+use crate::Provider;
+use blake2_rfc::blake2b::Blake2b;
+
+/// Verifier accepting any Sr25519 signature and recovering the same ECDSA key for any payload
+struct MockVerifier {
+	ecdsa_public: [u8; 33],
+}
+
+impl LegerVerifier for MockVerifier {
+	fn verify_sr25519(&self, _public: &Key, _payload: &[u8], _signature: &[u8; 64]) -> bool {
+		true
+	}
+
+	fn verify_ed25519(&self, _public: &Key, _payload: &[u8], _signature: &[u8; 64]) -> bool {
+		false
+	}
+
+	fn recover_ecdsa(&self, _message_hash: &[u8; 32], _signature: &[u8; 65]) -> Option<[u8; 33]> {
+		Some(self.ecdsa_public)
+	}
+}
+
+#[test]
+fn test_verify_dispatch() {
+	let verifier = MockVerifier { ecdsa_public: [0x02; 33] };
+	let key: Key = [1_u8; 32];
+
+	assert!(MultiSignature::Sr25519([0_u8; 64]).verify(&verifier, b"payload", &key));
+	assert!(!MultiSignature::Ed25519([0_u8; 64]).verify(&verifier, b"payload", &key));
+}
+
+#[test]
+fn test_verify_ecdsa_account() {
+	let verifier = MockVerifier { ecdsa_public: [0x02; 33] };
+
+	// ECDSA account is the Blake2b-256 of the compressed public key
+	let mut hasher = Blake2b::new(32);
+	hasher.update(verifier.ecdsa_public.as_ref());
+	let mut account: Key = [0_u8; 32];
+	account.copy_from_slice(hasher.finalize().as_bytes());
+
+	let signature = MultiSignature::Ecdsa([0_u8; 65]);
+	assert!(signature.verify(&verifier, b"payload", &account));
+	assert!(!signature.verify(&verifier, b"payload", &[0_u8; 32]));
+}
+
+/// Signer copying the signed payload into the signature
+struct EchoSigner {}
+
+impl LegerSigner for EchoSigner {
+	fn get_public(&self) -> Key {
+		[0_u8; 32]
+	}
+
+	fn sign(&self, payload: &[u8], signature: &mut [u8; 64]) {
+		signature.iter_mut().zip(payload.iter())
+			.for_each(|(t, f)| *t = *f);
+	}
+}
+
+#[test]
+fn test_sign_message_wrapping() {
+	let signer = EchoSigner {};
+	let account = Account::new(&signer);
+
+	let mut expected = [0_u8; 130];
+	expected[..2].copy_from_slice(b"0x");
+	let mut signed = [0_u8; 64];
+	signed[..20].copy_from_slice(b"<Bytes>hello</Bytes>");
+	hex::encode_to_slice::<[u8; 64]>(signed, &mut expected[2..]).unwrap();
+
+	let sig = account.sign_message(b"hello").unwrap();
+	assert_eq!(sig.as_bytes(), &expected[..]);
+
+	// already wrapped messages are not wrapped twice
+	let sig = account.sign_message(b"<Bytes>hello</Bytes>").unwrap();
+	assert_eq!(sig.as_bytes(), &expected[..]);
+
+	assert!(account.sign_message(&[0_u8; 256]).is_err());
+}
+
+#[test]
+fn test_empty_storage() {
+	let signer = EchoSigner {};
+	let mut account = Account::new(&signer);
+
+	// `state_getStorage` returns `null` for accounts which don't exist
+	let info = account.update_info(None).unwrap();
+	assert_eq!(info.data.free, 0);
+	assert_eq!(account.last_nonce(), 0);
+	assert!(account.info.is_some());
+
+	assert!(account.update_info(Some("0xzz")).is_err());
+}
+
+/// SCALE-encoded account info with nonce 5, ref count 1, free balance 1000 and reserved balance 2
+const ACCOUNT_INFO_HEX: &str = "0x0500000001000000\
+	e8030000000000000000000000000000\
+	02000000000000000000000000000000\
+	00000000000000000000000000000000\
+	00000000000000000000000000000000";
+
+#[test]
+fn test_decode_info() {
+	let info = decode_info(Some(ACCOUNT_INFO_HEX)).unwrap();
+	assert_eq!(info.nonce, 5);
+	assert_eq!(info.ref_count, 1);
+	assert_eq!(info.data.free, 1000);
+	assert_eq!(info.data.reserved, 2);
+	assert_eq!(info.data.misc_frozen, 0);
+
+	// values which are not exactly an account info are rejected, not zeroed
+	assert!(matches!(decode_info(Some("0x")), Err(AccountError::InvalidSize)));
+	assert!(matches!(decode_info(Some(&ACCOUNT_INFO_HEX[..ACCOUNT_INFO_HEX.len()-2])), Err(AccountError::InvalidSize)));
+	assert!(matches!(decode_info(Some(&format!("{}00", ACCOUNT_INFO_HEX))), Err(AccountError::InvalidSize)));
+	let invalid = format!("{}zz", &ACCOUNT_INFO_HEX[..ACCOUNT_INFO_HEX.len()-2]);
+	assert!(matches!(decode_info(Some(&invalid)), Err(AccountError::CannotConvert)));
+}
+
+#[test]
+fn test_get_info_params() {
+	buffers!(buffers, 512);
+	let state = MockState::new();
+	state.borrow_mut().receive(http_response(&format!("{{\"jsonrpc\":\"2.0\",\"result\":\"{}\",\"id\":1}}", ACCOUNT_INFO_HEX)));
+	state.borrow_mut().receive(http_response("{\"jsonrpc\":\"2.0\",\"result\":null,\"id\":2}"));
+	let endpoint = Endpoint::parse("http://127.0.0.1:9933").unwrap();
+	let mut provider = Provider::new(MockTransport::new(&state), endpoint, buffers).unwrap();
+
+	let signer = EchoSigner {};
+	let mut account = Account::new(&signer);
+	let mut key_hex = [0_u8; MAX_STORAGE_KEY_HEX_SIZE];
+	let key = account.storage_key().write_hex(&mut key_hex).unwrap();
+
+	assert_eq!(account.get_info(&mut provider).unwrap().nonce, 5);
+	assert_eq!(account.get_info_at(&mut provider, &[0xab; 32]).unwrap().data.free, 0);
+	// the last known info is not updated by reads at a given block
+	assert_eq!(account.last_nonce(), 5);
+
+	let sent = state.borrow().sent_text();
+	assert!(sent.contains(&format!("\"method\":\"state_getStorage\",\"params\":[\"{}\"]}}", key)));
+	assert!(sent.contains(&format!("\"method\":\"state_getStorage\",\"params\":[\"{}\",\"0x{}\"]}}", key, "ab".repeat(32))));
+}
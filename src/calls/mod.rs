@@ -4,4 +4,7 @@ pub mod transfer;
 /// Implement this trait to add extrinsic calls implementation
 pub trait Call {
 	fn encode(&self, payload: &mut [u8]) -> usize;
+
+	/// Size of the encoded call, so that buffers can be checked before calling `encode`
+	fn encoded_size(&self) -> usize;
 }
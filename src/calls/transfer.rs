@@ -39,4 +39,8 @@ impl Call for ExtrinsicTransferCall {
 
 		idx
 	}
+
+	fn encoded_size(&self) -> usize {
+		2 + self.dest_account.len() + self.amount.scale_compact_size()
+	}
 }
\ No newline at end of file
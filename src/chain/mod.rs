@@ -0,0 +1,130 @@
+use core::fmt;
+use serde::{Deserialize, Deserializer};
+use serde::de::{Visitor, SeqAccess, IgnoredAny};
+use crate::{ProviderError, decode_hash};
+
+#[cfg(test)]
+mod tests;
+
+/// State of the chain needed to build extrinsics
+#[derive(Debug, Clone, Copy)]
+pub struct ChainInfo {
+	pub genesis: [u8; 32],
+	/// Hash of the latest block
+	pub block_hash: [u8; 32],
+	pub spec_version: u32,
+	pub transaction_version: u32,
+}
+
+/// Versions of the runtime, returned by `state_getRuntimeVersion`
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeVersion<'a> {
+	pub spec_name: &'a str,
+	pub impl_name: &'a str,
+	pub authoring_version: u32,
+	pub spec_version: u32,
+	pub impl_version: u32,
+	/// Missing on older nodes, `0` in that case
+	#[serde(default)]
+	pub transaction_version: u32,
+}
+
+/// Health of the node, returned by `system_health`
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Health {
+	pub peers: u32,
+	pub is_syncing: bool,
+	/// `false` for dev chains, which don't need peers
+	pub should_have_peers: bool,
+}
+
+/// Decoded block header
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Header {
+	pub parent_hash: [u8; 32],
+	pub number: u32,
+	pub state_root: [u8; 32],
+	pub extrinsics_root: [u8; 32],
+	/// Number of digest items (logs), which are not decoded
+	pub digest_len: usize,
+}
+
+/// Header returned by `chain_getHeader`, with hashes and number as hex strings
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct JsonHeader<'a> {
+	parent_hash: &'a str,
+	number: &'a str,
+	state_root: &'a str,
+	extrinsics_root: &'a str,
+	digest: Digest,
+}
+
+#[derive(Deserialize)]
+struct Digest {
+	logs: LogCount,
+}
+
+/// Number of digest items, skipped without being parsed
+struct LogCount(usize);
+
+struct LogCountVisitor;
+
+impl<'de> Visitor<'de> for LogCountVisitor {
+	type Value = LogCount;
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("a list of digest items")
+	}
+
+	fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+		let mut count = 0_usize;
+		while seq.next_element::<IgnoredAny>()?.is_some() {
+			count += 1;
+		}
+
+		Ok(LogCount(count))
+	}
+}
+
+impl<'de> Deserialize<'de> for LogCount {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		deserializer.deserialize_seq(LogCountVisitor)
+	}
+}
+
+impl Header {
+	/// Decodes the hashes and number of the header returned by the node
+	///
+	/// ## Errors
+	/// * `CannotParse`: a hash or the number is not a valid hex string
+	pub(crate) fn decode(header: &JsonHeader) -> Result<Header, ProviderError> {
+		let number = header.number.strip_prefix("0x").unwrap_or(header.number);
+
+		Ok(Header {
+			parent_hash: decode_hash(header.parent_hash)?,
+			number: u32::from_str_radix(number, 16).map_err(|_| ProviderError::CannotParse)?,
+			state_root: decode_hash(header.state_root)?,
+			extrinsics_root: decode_hash(header.extrinsics_root)?,
+			digest_len: header.digest.logs.0,
+		})
+	}
+}
+
+pub trait Chain {
+	type Error: core::fmt::Debug;
+
+	fn get_block_hash(&mut self, number: Option<[usize; 1]>) -> Result<[u8; 32], Self::Error>;
+	fn get_genesis_block_hash(&mut self) -> Result<[u8; 32], Self::Error>;
+	/// Returns the hash of the last finalized block
+	fn get_finalized_head(&mut self) -> Result<[u8; 32], Self::Error>;
+	/// Returns the header of the block `hash`, or of the latest block
+	fn get_header(&mut self, hash: Option<&[u8; 32]>) -> Result<Header, Self::Error>;
+	/// Returns the number of the latest block, e.g. to compute mortal eras or confirmations
+	fn get_block_number(&mut self) -> Result<u32, Self::Error>;
+	/// Returns the genesis hash, the latest block hash and the runtime versions,
+	/// fetched at once when possible.
+	fn get_chain_info(&mut self) -> Result<ChainInfo, Self::Error>;
+}
@@ -0,0 +1,55 @@
+extern crate std;
+
+use std::format;
+use std::string::String;
+use crate::{Provider, ProviderError};
+use crate::chain::{Chain, Header, JsonHeader};
+use crate::rpc::Endpoint;
+use crate::rpc::mock::{MockState, MockTransport, http_response, buffers};
+
+/// Header as returned by `chain_getHeader`, with the block number `number`
+fn json_header(number: &str) -> String {
+	format!("{{\"digest\":{{\"logs\":[\"0x0642414245b50103\",\"0x0542414245\"]}},\
+		\"extrinsicsRoot\":\"0x0101010101010101010101010101010101010101010101010101010101010101\",\
+		\"number\":\"{}\",\
+		\"parentHash\":\"0x0202020202020202020202020202020202020202020202020202020202020202\",\
+		\"stateRoot\":\"0x0303030303030303030303030303030303030303030303030303030303030303\"}}", number)
+}
+
+#[test]
+fn test_decode_header() {
+	let res = json_header("0x1a2b");
+	let (json, _) = serde_json_core::from_str::<JsonHeader>(&res).unwrap();
+	let header = Header::decode(&json).unwrap();
+
+	assert_eq!(header.number, 0x1a2b);
+	assert_eq!(header.parent_hash, [2_u8; 32]);
+	assert_eq!(header.state_root, [3_u8; 32]);
+	assert_eq!(header.extrinsics_root, [1_u8; 32]);
+	assert_eq!(header.digest_len, 2);
+}
+
+#[test]
+fn test_decode_malformed_number() {
+	for number in &["0xzz", "", "0x100000000"] {
+		let res = json_header(number);
+		let (json, _) = serde_json_core::from_str::<JsonHeader>(&res).unwrap();
+		assert!(matches!(Header::decode(&json), Err(ProviderError::CannotParse)));
+	}
+}
+
+#[test]
+fn test_get_header_at() {
+	buffers!(buffers, 512);
+	let state = MockState::new();
+	state.borrow_mut().receive(http_response(&format!("{{\"jsonrpc\":\"2.0\",\"result\":{},\"id\":1}}", json_header("0x2a"))));
+	let endpoint = Endpoint::parse("http://127.0.0.1:9933").unwrap();
+	let mut provider = Provider::new(MockTransport::new(&state), endpoint, buffers).unwrap();
+
+	let header = provider.get_header(Some(&[0xcd; 32])).unwrap();
+	assert_eq!(header.number, 42);
+	assert_eq!(header.parent_hash, [2_u8; 32]);
+
+	let expected = format!("\"method\":\"chain_getHeader\",\"params\":[\"0x{}\"]}}", "cd".repeat(32));
+	assert!(state.borrow().sent_text().contains(&expected));
+}
@@ -1,18 +1,95 @@
-use crate::account::Account;
+use crate::account::{Account, Key, MultiSignature};
 use crate::scale::Compact;
-use crate::{ProviderError, MAXIMUM_PAYLOAD_SIZE_BYTES};
-use crate::chain::Chain;
+use crate::ProviderError;
+use crate::chain::{Chain, ChainInfo};
 use crate::calls::Call;
+use core::str::from_utf8;
+use blake2_rfc::blake2b::Blake2b;
 
+#[cfg(test)]
+mod tests;
+
+/// Signature payloads larger than this size are hashed before being signed
+const MAXIMUM_UNHASHED_PAYLOAD_SIZE_BYTES: usize = 256;
+
+#[derive(Debug, Clone, Copy)]
 pub enum ExtrinsicEra {
 	Immortal,
-	Mortal,
+	/// Created with [`ExtrinsicEra::mortal`](#method.mortal)
+	Mortal(MortalEra),
+}
+
+/// Period and phase of a mortal era, the period being a power of two between 4 and 65536
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MortalEra {
+	period: u64,
+	phase: u64,
+}
+
+impl MortalEra {
+	/// Number of blocks the extrinsic is valid for
+	pub fn period(&self) -> u64 {
+		self.period
+	}
+
+	/// Position of the first block of the era in its period (quantized)
+	pub fn phase(&self) -> u64 {
+		self.phase
+	}
+}
+
+impl ExtrinsicEra {
+	/// Creates a mortal era, valid for `period` blocks from the `current` block number.
+	/// As in Substrate, `period` is rounded up to a power of two, between 4 and 65536.
+	pub fn mortal(period: u64, current: u64) -> ExtrinsicEra {
+		let period = period.checked_next_power_of_two()
+			.unwrap_or(1 << 16)
+			.max(4)
+			.min(1 << 16);
+		let phase = current % period;
+		let quantize_factor = (period >> 12).max(1);
+		let quantized_phase = phase / quantize_factor * quantize_factor;
+
+		ExtrinsicEra::Mortal(MortalEra {
+			period,
+			phase: quantized_phase,
+		})
+	}
+
+	/// Returns the number of the first block of the era, its hash must be used as the checkpoint
+	/// block hash in the signature payload. Immortal era starts at the genesis block.
+	pub fn birth(&self, current: u64) -> u64 {
+		match self {
+			ExtrinsicEra::Immortal => 0,
+			ExtrinsicEra::Mortal(MortalEra { period, phase }) => {
+				(current.max(*phase) - phase) / period * period + phase
+			}
+		}
+	}
+
+	/// SCALE encodes the era into `payload` and returns the number of bytes written (1 or 2)
+	pub fn encode(&self, payload: &mut [u8]) -> usize {
+		match self {
+			ExtrinsicEra::Immortal => {
+				payload[0] = 0x00;
+				1
+			}
+			ExtrinsicEra::Mortal(MortalEra { period, phase }) => {
+				let quantize_factor = (*period >> 12).max(1);
+				let encoded = (period.trailing_zeros() - 1).max(1).min(15) as u16
+					| ((phase / quantize_factor) << 4) as u16;
+
+				payload[..2].copy_from_slice(encoded.to_le_bytes().as_ref());
+				2
+			}
+		}
+	}
 }
 
 
 pub struct ExtrinsicPayload<'a> {
 	method: &'a dyn Call,
-	era: [u8; 1], // immortal: 0x00
+	era: ExtrinsicEra,
 	nonce: u32, // SCALE encoded
 	tip: u128, // SCALE encoded
 	spec_version: u32,
@@ -26,53 +103,88 @@ pub trait ExtrinsicCalls {
 
 	fn submit_extrinsic(&mut self, author: &mut Account, method: &dyn Call) -> Result<&str, Self::Error>;
 
+	fn submit_extrinsic_with_buffer(&mut self, author: &mut Account, method: &dyn Call, buf: &mut [u8]) -> Result<&str, Self::Error>;
+
+	fn submit_raw_extrinsic(&mut self, extrinsic: &[u8]) -> Result<&str, Self::Error>;
+
+	fn submit_raw_extrinsic_with_buffer(&mut self, extrinsic: &[u8], buf: &mut [u8]) -> Result<&str, Self::Error>;
+
 	fn balance_transfer(&mut self, author: &mut Account, dest_account: &[u8; 32], amount: u128)
 						-> Result<&str, Self::Error>;
 }
 
 impl<'a> ExtrinsicPayload<'a> {
-	/// Creates a new `ExtrinsicPayload` structure to be serialized.
+	/// Creates a new immortal `ExtrinsicPayload` structure to be serialized.
 	///
 	/// ## Errors
-	/// Initialization of the structure needs access to the blockchain to get genesis and block hash,
-	/// if those have not been fetched before.
+	/// Initialization of the structure needs access to the blockchain to get genesis and block hash
+	/// (genesis is fetched once), and the runtime versions, see [`Chain::get_chain_info`](../chain/trait.Chain.html#tymethod.get_chain_info).
 	/// Thus, creating an extrinsic can return an error; see `ProviderError`
 	pub fn new(chain: &mut dyn Chain<Error=ProviderError>, call: &'a dyn Call, nonce: u32) -> Result<ExtrinsicPayload<'a>, ProviderError> {
-		let genesis = chain.get_genesis_block_hash()?;
-		let block_hash = chain.get_block_hash(None)?;
-		//let transaction_version =
-		Ok(ExtrinsicPayload {
+		let info = chain.get_chain_info()?;
+
+		Ok(ExtrinsicPayload::from_chain_info(call, nonce, &info))
+	}
+
+	/// Creates a new immortal `ExtrinsicPayload` from chain info fetched beforehand
+	pub fn from_chain_info(call: &'a dyn Call, nonce: u32, info: &ChainInfo) -> ExtrinsicPayload<'a> {
+		ExtrinsicPayload::new_offline(call, nonce, ExtrinsicEra::Immortal, info.genesis, info.block_hash,
+									  info.spec_version, info.transaction_version)
+	}
+
+	/// Creates a new `ExtrinsicPayload` structure without any access to the blockchain,
+	/// so that extrinsics can be built and signed on air-gapped devices.
+	///
+	/// * `block_hash` is the hash of the first block of a mortal `era` (see [`ExtrinsicEra::birth`](enum.ExtrinsicEra.html#method.birth)),
+	/// it is not used for immortal extrinsics.
+	/// * `spec_version` and `transaction_version` must match the runtime of the chain.
+	pub fn new_offline(call: &'a dyn Call, nonce: u32, era: ExtrinsicEra, genesis: [u8; 32], block_hash: [u8; 32],
+					   spec_version: u32, transaction_version: u32) -> ExtrinsicPayload<'a> {
+		ExtrinsicPayload {
 			method: call,
-			era: [0x00], // immortal TODO implement Mortal era
+			era,
 			nonce,
 			tip: 0,
-			spec_version: 1, // TODO get from `runtime_version`
-			transaction_version: 1,  // TODO get from `runtime_version`
+			spec_version,
+			transaction_version,
 			genesis,
 			block_hash
-		})
+		}
+	}
+
+	/// Size of the SCALE encoded era, nonce and tip, found in both the signature payload and the extrinsic
+	fn extra_size(&self) -> usize {
+		let mut era = [0_u8; 2];
+		self.era.encode(&mut era) + self.nonce.scale_compact_size() + self.tip.scale_compact_size()
+	}
+
+	/// Size of the signature payload, before being hashed
+	fn signature_payload_size(&self) -> usize {
+		// call, extra, spec and transaction versions, genesis and checkpoint block hashes
+		self.method.encoded_size() + self.extra_size() + 4 + 4 + 32 + 32
 	}
 
-	/// Generates the signature payload used to compute a signature
-	/// The call block located at the beginning of the sig payload is to be used in the
-	/// extrinsic payload.
-	/// Thus, two sizes are returned: the call size and the signature payload size
-	fn signature_payload(&self, payload: &mut [u8]) -> (usize, usize) {
+	/// Size of the extrinsic once signed with `signature`
+	fn signed_tx_size(&self, signature: &MultiSignature) -> usize {
+		// version, signer, signature, extra and call
+		1 + 32 + signature.encoded_size() + self.extra_size() + self.method.encoded_size()
+	}
+
+	/// Generates the signature payload used to compute a signature.
+	/// `payload` must be at least `signature_payload_size()` long.
+	/// The signature payload size is returned.
+	fn signature_payload(&self, payload: &mut [u8]) -> usize {
 		// method
 		let mut idx = self.method.encode(&mut payload[0..]);
-		let call_size = idx;
 
 		// era
-		payload[idx] = self.era[0];
-		idx += 1;
+		idx += self.era.encode(&mut payload[idx..]);
 
 		// nonce
-		let nonce = self.nonce as u128;
-		idx += nonce.scale_compact(&mut payload[idx..]);
+		idx += self.nonce.scale_compact(&mut payload[idx..]);
 
-		// tip: `Balance` used to prioritize transaction, let's keep it 0
-		let tip = 0_u128;
-		idx += tip.scale_compact(&mut payload[idx..]);
+		// tip: `Balance` used to prioritize transaction
+		idx += self.tip.scale_compact(&mut payload[idx..]);
 
 		// spec version
 		let mut u32_buf = self.spec_version.to_le_bytes();
@@ -91,70 +203,183 @@ impl<'a> ExtrinsicPayload<'a> {
 		// hash of the “checkpoint block”, which is to say the first block of the era specified
 		// by the era field. If just making the transaction “immmortal”, then the genesis hash
 		// of the blockchain should be used.
-		if self.era[0] == 0 {
+		if let ExtrinsicEra::Immortal = self.era {
 			payload[idx..idx+self.genesis.len()].copy_from_slice(self.genesis.as_ref());
 			idx += self.genesis.len();
 		} else {
-			unimplemented!();
+			payload[idx..idx+self.block_hash.len()].copy_from_slice(self.block_hash.as_ref());
+			idx += self.block_hash.len();
 		}
 
-		(call_size, idx)
+		idx
+	}
+
+	/// Generates the exact payload to be signed, without signing it, so that the signature can be
+	/// computed on a separate device (Parity Signer / UOS workflow).
+	/// As in Substrate, a payload larger than 256 bytes is replaced by its Blake2b (32 bytes) hash.
+	/// The actual size of the payload is returned.
+	///
+	/// ## Errors
+	/// * `InvalidSize`: `payload` buffer is not large enough, the needed size is returned
+	pub fn signing_payload(&self, payload: &mut [u8]) -> Result<usize, ProviderError> {
+		let size = self.signature_payload_size();
+		if payload.len() < size {
+			return Err(ProviderError::InvalidSize(size))
+		}
+
+		self.signature_payload(payload);
+
+		if size > MAXIMUM_UNHASHED_PAYLOAD_SIZE_BYTES {
+			let mut hasher = Blake2b::new(32);
+			hasher.update(payload[..size].as_ref());
+			payload[..32].copy_from_slice(hasher.finalize().as_bytes());
+			Ok(32)
+		} else {
+			Ok(size)
+		}
 	}
 
 	/// Generates the extrinsic payload to be sent and put it into `signed_tx`.
+	/// The same buffer is used to compute the signature payload.
 	/// The actual size of the payload is returned.
 	/// Payload is signed using the account `sender_account`.
 	///
 	/// ## Errors
-	/// * returns `0` if `signed_tx` buffer is not large enough
-	pub fn signed_tx(&self, sender_account: &Account, signed_tx: &mut [u8; MAXIMUM_PAYLOAD_SIZE_BYTES]) -> usize {
-		// we keep the packed call (module index, call index & params) in a temporary buffer
-		// as we need it in the final payload
-		let mut temp_packed_call = [0_u8; 64];
+	/// * `InvalidSize`: `signed_tx` buffer is not large enough, the needed size is returned
+	pub fn signed_tx(&self, sender_account: &Account, signed_tx: &mut [u8]) -> Result<usize, ProviderError> {
+		let size = self.signed_tx_size(&MultiSignature::Sr25519([0_u8; 64]))
+			.max(self.signature_payload_size());
+		if signed_tx.len() < size {
+			return Err(ProviderError::InvalidSize(size))
+		}
 
 		// compose the extrinsic payload that is about to be signed
-		let (packed_call_size, payload_size) = self.signature_payload(signed_tx.as_mut());
+		let payload_size = self.signing_payload(signed_tx)?;
 
 		// sign the payload
 		let mut signature = [0_u8; 64];
 		sender_account.sign_tx(signed_tx[..payload_size].as_mut(), &mut signature);
 
-		// copy the `call` part to be sent along with the extrinsic signature
-		temp_packed_call[..packed_call_size].copy_from_slice(signed_tx[..packed_call_size].as_ref());
+		// the signature payload isn't needed anymore, `signed_tx` can be overwritten
+		self.signed_tx_from_signature(&sender_account.u8a(), &MultiSignature::Sr25519(signature), signed_tx)
+	}
+
+	/// Generates the extrinsic payload to be sent using a `signature` computed externally
+	/// over the [`signing_payload`](#method.signing_payload) by the `signer` account.
+	/// The actual size of the payload is returned.
+	///
+	/// ## Errors
+	/// * `InvalidSize`: `signed_tx` buffer is not large enough, the needed size is returned
+	pub fn signed_tx_from_signature(&self, signer: &Key, signature: &MultiSignature,
+									signed_tx: &mut [u8]) -> Result<usize, ProviderError> {
+		let size = self.signed_tx_size(signature);
+		if signed_tx.len() < size {
+			return Err(ProviderError::InvalidSize(size))
+		}
 
+		// signed extrinsic, version 4
 		signed_tx[0] = 0x84;
-		// signed_tx[1] = 0xFF;
 
 		let mut idx = 1_usize;
 
-		signed_tx[idx..].iter_mut().zip(sender_account.u8a().iter())
-			.for_each(|(t, f)| *t = *f);
-		idx += sender_account.u8a().len();
-
-		signed_tx[idx] = 0x01;
-		idx += 1;
+		signed_tx[idx..idx+signer.len()].copy_from_slice(signer.as_ref());
+		idx += signer.len();
 
-		signed_tx[idx..].iter_mut().zip(signature.iter())
-			.for_each(|(t, f)| *t = *f);
-		idx += signature.len();
+		idx += signature.encode(&mut signed_tx[idx..]);
 
-		// era, immortal
-		signed_tx[idx] = self.era[0];
-		idx += 1;
+		idx += self.era.encode(&mut signed_tx[idx..]);
 
 		idx += self.nonce.scale_compact(&mut signed_tx[idx..]);
 		idx += self.tip.scale_compact(&mut signed_tx[idx..]);
 
-		// append packed call
-		signed_tx[idx..idx+packed_call_size].copy_from_slice(temp_packed_call[..packed_call_size].as_ref());
-		idx += packed_call_size;
+		// append packed call (module index, call index & params)
+		idx += self.method.encode(&mut signed_tx[idx..]);
 
-		return if idx < signed_tx.len() {
-			idx
-		} else {
-			0
+		Ok(idx)
+	}
+
+	/// Generates the signed extrinsic and writes it into `buf` as an hex string, prefixed with
+	/// its SCALE compact length, as expected by `author_submitExtrinsic`.
+	/// The extrinsic can then be handed to any device in charge of submitting it.
+	///
+	/// ## Errors
+	/// * `InvalidSize`: `buf` isn't large enough, the needed size is returned
+	pub fn signed_tx_hex<'b>(&self, sender_account: &Account, buf: &'b mut [u8]) -> Result<&'b str, ProviderError> {
+		let len = hex_size(self.signed_tx_size(&MultiSignature::Sr25519([0_u8; 64])));
+		if buf.len() < len {
+			return Err(ProviderError::InvalidSize(len))
 		}
+
+		let size = self.signed_tx(sender_account, buf)?;
+		encode_hex_in_place(buf, size)
+	}
+
+	/// Same as [`signed_tx_hex`](#method.signed_tx_hex) using a `signature` computed externally
+	/// over the [`signing_payload`](#method.signing_payload) by the `signer` account.
+	///
+	/// ## Errors
+	/// * `InvalidSize`: `buf` isn't large enough, the needed size is returned
+	pub fn signed_tx_hex_from_signature<'b>(&self, signer: &Key, signature: &MultiSignature, buf: &'b mut [u8]) -> Result<&'b str, ProviderError> {
+		let len = hex_size(self.signed_tx_size(signature));
+		if buf.len() < len {
+			return Err(ProviderError::InvalidSize(len))
+		}
+
+		let size = self.signed_tx_from_signature(signer, signature, buf)?;
+		encode_hex_in_place(buf, size)
+	}
+}
+
+/// Size of an extrinsic of `extrinsic_size` bytes once encoded by [`encode_hex`](fn.encode_hex.html)
+pub(crate) fn hex_size(extrinsic_size: usize) -> usize {
+	2 + ((extrinsic_size as u32).scale_compact_size() + extrinsic_size) * 2
+}
+
+/// Writes `extrinsic` into `buf` as an hex string starting with "0x",
+/// with the SCALE compact length of the extrinsic as a header.
+///
+/// ## Errors
+/// * `InvalidSize`: `buf` isn't large enough, the needed size is returned
+pub(crate) fn encode_hex<'b>(extrinsic: &[u8], buf: &'b mut [u8]) -> Result<&'b str, ProviderError> {
+	let len = hex_size(extrinsic.len());
+	if buf.len() < len {
+		return Err(ProviderError::InvalidSize(len))
 	}
 
+	buf[..extrinsic.len()].copy_from_slice(extrinsic);
+	encode_hex_in_place(buf, extrinsic.len())
 }
 
+/// Same as [`encode_hex`](fn.encode_hex.html) with the extrinsic located at the beginning of `buf`,
+/// so that only one buffer is needed.
+///
+/// ## Errors
+/// * `InvalidSize`: `buf` isn't large enough, the needed size is returned
+fn encode_hex_in_place(buf: &mut [u8], size: usize) -> Result<&str, ProviderError> {
+	let mut header = [0_u8; 5];
+	let header_size = (size as u32).scale_compact(&mut header);
+
+	let len = 2 + (header_size + size) * 2;
+	if buf.len() < len {
+		return Err(ProviderError::InvalidSize(len))
+	}
+
+	// move the extrinsic to the end of the hex string, so that bytes can be encoded from the
+	// beginning without overwriting the ones that haven't been encoded yet
+	let offset = len - size;
+	buf.copy_within(0..size, offset);
+
+	buf[0] = 0x30; // "0"
+	buf[1] = 0x78; // "x"
+	if hex::encode_to_slice(&header[..header_size], &mut buf[2..2+header_size*2]).is_err() {
+		return Err(ProviderError::InvalidSize(len))
+	}
+
+	let start = 2 + header_size * 2;
+	(0..size).try_for_each(|i| {
+		let byte = [buf[offset + i]];
+		hex::encode_to_slice(byte, &mut buf[start+i*2..start+i*2+2])
+	}).map_err(|_| ProviderError::InvalidSize(len))?;
+
+	from_utf8(buf[..len].as_ref()).map_err(|_| ProviderError::CannotParse)
+}
@@ -0,0 +1,105 @@
+use crate::extrinsic::{ExtrinsicEra, MortalEra, ExtrinsicPayload, encode_hex};
+use crate::account::{Account, Key, LegerSigner, MultiSignature};
+use crate::calls::transfer::ExtrinsicTransferCall;
+use crate::ProviderError;
+
+#[test]
+fn test_mortal_era() {
+	let mut payload = [0_u8; 2];
+
+	// values from Substrate's `sp_runtime::generic::Era` tests
+	let era = ExtrinsicEra::mortal(64, 42);
+	assert_eq!(era.encode(&mut payload), 2);
+	assert_eq!(payload, [5 + 42 % 16 * 16, 42 / 16]);
+	assert_eq!(era.birth(42), 42);
+	assert_eq!(era.birth(67), 42);
+	assert_eq!(era.birth(106), 106);
+
+	// periods are rounded up to a power of two, within bounds
+	assert!(matches!(ExtrinsicEra::mortal(0, 5), ExtrinsicEra::Mortal(era) if era == MortalEra { period: 4, phase: 1 }));
+	assert!(matches!(ExtrinsicEra::mortal(100, 5), ExtrinsicEra::Mortal(era) if era.period() == 128));
+	assert!(matches!(ExtrinsicEra::mortal(u64::MAX, 5), ExtrinsicEra::Mortal(era) if era.period() == 65536));
+
+	let era = ExtrinsicEra::mortal(32768, 20000);
+	assert_eq!(era.encode(&mut payload), 2);
+	assert_eq!(payload, [(14 + 2500 % 16 * 16) as u8, (2500 / 16) as u8]);
+
+	assert_eq!(ExtrinsicEra::Immortal.encode(&mut payload), 1);
+	assert_eq!(payload[0], 0);
+}
+
+#[test]
+fn test_encode_hex() {
+	let mut buf = [0_u8; 10];
+	assert_eq!(encode_hex(&[1, 2, 3], &mut buf).unwrap(), "0x0c010203");
+
+	let mut buf = [0_u8; 9];
+	assert!(encode_hex(&[1, 2, 3], &mut buf).is_err());
+}
+
+/// Signer using the first bytes of the payload as signature
+struct TestSigner {}
+
+impl LegerSigner for TestSigner {
+	fn get_public(&self) -> Key {
+		[0xAA_u8; 32]
+	}
+
+	fn sign(&self, payload: &[u8], signature: &mut [u8; 64]) {
+		signature.iter_mut().zip(payload.iter())
+			.for_each(|(t, f)| *t = *f);
+	}
+}
+
+#[test]
+fn test_external_signature() {
+	let call = ExtrinsicTransferCall::new(&[0xBB_u8; 32], 1_000);
+	let extrinsic = ExtrinsicPayload::new_offline(&call, 3, ExtrinsicEra::mortal(64, 42),
+												  [0x01_u8; 32], [0x02_u8; 32], 1, 1);
+
+	let signer = TestSigner {};
+	let account = Account::new(&signer);
+
+	let mut signed_internally = [0_u8; 256];
+	let size = extrinsic.signed_tx(&account, &mut signed_internally).unwrap();
+
+	// sign the exported payload "externally"
+	let mut payload = [0_u8; 256];
+	let payload_size = extrinsic.signing_payload(&mut payload).unwrap();
+	let mut signature = [0_u8; 64];
+	signer.sign(payload[..payload_size].as_ref(), &mut signature);
+
+	let mut signed_externally = [0_u8; 256];
+	let external_size = extrinsic.signed_tx_from_signature(&signer.get_public(),
+														   &MultiSignature::Sr25519(signature),
+														   &mut signed_externally).unwrap();
+
+	assert_eq!(size, external_size);
+	assert_eq!(signed_internally[..size], signed_externally[..size]);
+}
+
+#[test]
+fn test_signed_tx_size() {
+	let call = ExtrinsicTransferCall::new(&[0xBB_u8; 32], 1_000);
+	let extrinsic = ExtrinsicPayload::new_offline(&call, 3, ExtrinsicEra::Immortal,
+												  [0x01_u8; 32], [0x02_u8; 32], 1, 1);
+
+	let signer = TestSigner {};
+	let account = Account::new(&signer);
+
+	// version + signer + signature + era + nonce + tip + call
+	let size = 1 + 32 + 65 + 1 + 1 + 1 + (2 + 32 + 2);
+
+	let mut buf = [0_u8; 256];
+	assert_eq!(extrinsic.signed_tx(&account, &mut buf).unwrap(), size);
+
+	match extrinsic.signed_tx(&account, &mut buf[..size-1]) {
+		Err(ProviderError::InvalidSize(needed)) => assert_eq!(needed, size),
+		_ => panic!("Buffer should be too small"),
+	}
+
+	// "0x" + 2-byte compact length + extrinsic, as hex
+	let hex = extrinsic.signed_tx_hex(&account, &mut buf).unwrap();
+	assert_eq!(hex.len(), 2 + (2 + size) * 2);
+	assert!(hex.starts_with("0x250284aaaa"));
+}
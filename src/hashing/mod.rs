@@ -0,0 +1,174 @@
+use blake2_rfc::blake2b::Blake2b;
+
+#[cfg(test)]
+mod tests;
+
+const PRIME64_1: u64 = 0x9E37_79B1_85EB_CA87;
+const PRIME64_2: u64 = 0xC2B2_AE3D_27D4_EB4F;
+const PRIME64_3: u64 = 0x1656_67B1_9E37_79F9;
+const PRIME64_4: u64 = 0x85EB_CA77_C2B2_AE63;
+const PRIME64_5: u64 = 0x27D4_EB2F_1656_67C5;
+
+fn read_u64(data: &[u8]) -> u64 {
+	let mut bytes = [0_u8; 8];
+	bytes.copy_from_slice(&data[..8]);
+	u64::from_le_bytes(bytes)
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+	let mut bytes = [0_u8; 4];
+	bytes.copy_from_slice(&data[..4]);
+	u32::from_le_bytes(bytes)
+}
+
+fn round(acc: u64, input: u64) -> u64 {
+	acc.wrapping_add(input.wrapping_mul(PRIME64_2))
+		.rotate_left(31)
+		.wrapping_mul(PRIME64_1)
+}
+
+fn merge_round(acc: u64, val: u64) -> u64 {
+	(acc ^ round(0, val))
+		.wrapping_mul(PRIME64_1)
+		.wrapping_add(PRIME64_4)
+}
+
+/// XXH64 of `data` with `seed`
+fn xxhash64(data: &[u8], seed: u64) -> u64 {
+	let mut rest = data;
+	let mut hash = if data.len() >= 32 {
+		let mut v = [
+			seed.wrapping_add(PRIME64_1).wrapping_add(PRIME64_2),
+			seed.wrapping_add(PRIME64_2),
+			seed,
+			seed.wrapping_sub(PRIME64_1),
+		];
+		while rest.len() >= 32 {
+			for (i, acc) in v.iter_mut().enumerate() {
+				*acc = round(*acc, read_u64(&rest[i*8..]));
+			}
+			rest = &rest[32..];
+		}
+
+		let mut hash = v[0].rotate_left(1)
+			.wrapping_add(v[1].rotate_left(7))
+			.wrapping_add(v[2].rotate_left(12))
+			.wrapping_add(v[3].rotate_left(18));
+		for acc in v.iter() {
+			hash = merge_round(hash, *acc);
+		}
+		hash
+	} else {
+		seed.wrapping_add(PRIME64_5)
+	};
+
+	hash = hash.wrapping_add(data.len() as u64);
+
+	while rest.len() >= 8 {
+		hash ^= round(0, read_u64(rest));
+		hash = hash.rotate_left(27).wrapping_mul(PRIME64_1).wrapping_add(PRIME64_4);
+		rest = &rest[8..];
+	}
+	if rest.len() >= 4 {
+		hash ^= u64::from(read_u32(rest)).wrapping_mul(PRIME64_1);
+		hash = hash.rotate_left(23).wrapping_mul(PRIME64_2).wrapping_add(PRIME64_3);
+		rest = &rest[4..];
+	}
+	for byte in rest {
+		hash ^= u64::from(*byte).wrapping_mul(PRIME64_5);
+		hash = hash.rotate_left(11).wrapping_mul(PRIME64_1);
+	}
+
+	// avalanche
+	hash ^= hash >> 33;
+	hash = hash.wrapping_mul(PRIME64_2);
+	hash ^= hash >> 29;
+	hash = hash.wrapping_mul(PRIME64_3);
+	hash ^ (hash >> 32)
+}
+
+/// Fills `hash` with the XXH64 of `data` using seeds `0, 1, ...`, as in Substrate
+fn twox(data: &[u8], hash: &mut [u8]) {
+	for (seed, chunk) in hash.chunks_mut(8).enumerate() {
+		chunk.copy_from_slice(&xxhash64(data, seed as u64).to_le_bytes());
+	}
+}
+
+pub fn twox_64(data: &[u8]) -> [u8; 8] {
+	xxhash64(data, 0).to_le_bytes()
+}
+
+pub fn twox_128(data: &[u8]) -> [u8; 16] {
+	let mut hash = [0_u8; 16];
+	twox(data, &mut hash);
+	hash
+}
+
+pub fn twox_256(data: &[u8]) -> [u8; 32] {
+	let mut hash = [0_u8; 32];
+	twox(data, &mut hash);
+	hash
+}
+
+pub fn blake2_128(data: &[u8]) -> [u8; 16] {
+	let mut hash = [0_u8; 16];
+	let mut hasher = Blake2b::new(16);
+	hasher.update(data);
+	hash.copy_from_slice(hasher.finalize().as_bytes());
+	hash
+}
+
+pub fn blake2_256(data: &[u8]) -> [u8; 32] {
+	let mut hash = [0_u8; 32];
+	let mut hasher = Blake2b::new(32);
+	hasher.update(data);
+	hash.copy_from_slice(hasher.finalize().as_bytes());
+	hash
+}
+
+/// Hashers used for the keys of storage maps, see the `hasher` of each key in the pallet metadata
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StorageHasher {
+	Blake2_128,
+	Blake2_256,
+	/// Blake2b (16 bytes) of the key followed by the key, for keys set by users (e.g. account IDs)
+	Blake2_128Concat,
+	Twox128,
+	Twox256,
+	/// XXH64 of the key followed by the key, for keys which cannot be chosen freely (e.g. indexes)
+	Twox64Concat,
+	/// Key written as is
+	Identity,
+}
+
+impl StorageHasher {
+	/// Size of the hashed `key`
+	pub fn hashed_len(self, key: &[u8]) -> usize {
+		match self {
+			StorageHasher::Blake2_128 | StorageHasher::Twox128 => 16,
+			StorageHasher::Blake2_256 | StorageHasher::Twox256 => 32,
+			StorageHasher::Blake2_128Concat => 16 + key.len(),
+			StorageHasher::Twox64Concat => 8 + key.len(),
+			StorageHasher::Identity => key.len(),
+		}
+	}
+
+	/// Writes the hashed `key` into `out`, which must be `hashed_len()` long
+	pub(crate) fn hash_into(self, key: &[u8], out: &mut [u8]) {
+		match self {
+			StorageHasher::Blake2_128 => out.copy_from_slice(&blake2_128(key)),
+			StorageHasher::Blake2_256 => out.copy_from_slice(&blake2_256(key)),
+			StorageHasher::Blake2_128Concat => {
+				out[..16].copy_from_slice(&blake2_128(key));
+				out[16..].copy_from_slice(key);
+			}
+			StorageHasher::Twox128 => out.copy_from_slice(&twox_128(key)),
+			StorageHasher::Twox256 => out.copy_from_slice(&twox_256(key)),
+			StorageHasher::Twox64Concat => {
+				out[..8].copy_from_slice(&twox_64(key));
+				out[8..].copy_from_slice(key);
+			}
+			StorageHasher::Identity => out.copy_from_slice(key),
+		}
+	}
+}
@@ -0,0 +1,32 @@
+use crate::hashing::{twox_64, twox_128, twox_256, StorageHasher};
+
+#[test]
+fn test_twox() {
+	assert_eq!(twox_64(b""), [0x99, 0xe9, 0xd8, 0x51, 0x37, 0xdb, 0x46, 0xef]);
+
+	let mut expected = [0_u8; 16];
+	hex::decode_to_slice("26aa394eea5630e07c48ae0c9558cef7", &mut expected as &mut [u8]).unwrap();
+	assert_eq!(twox_128(b"System"), expected);
+	hex::decode_to_slice("c2261276cc9d1f8598ea4b6a74b15c2f", &mut expected as &mut [u8]).unwrap();
+	assert_eq!(twox_128(b"Balances"), expected);
+
+	// first half is the twox_128
+	assert_eq!(twox_256(b"System")[..16], twox_128(b"System"));
+}
+
+#[test]
+fn test_concat_hashers() {
+	let key = [7_u8; 4];
+	let mut out = [0_u8; 20];
+
+	assert_eq!(StorageHasher::Blake2_128Concat.hashed_len(&key), 20);
+	StorageHasher::Blake2_128Concat.hash_into(&key, &mut out);
+	assert_eq!(out[16..], key);
+
+	assert_eq!(StorageHasher::Twox64Concat.hashed_len(&key), 12);
+	StorageHasher::Twox64Concat.hash_into(&key, &mut out[..12]);
+	assert_eq!(out[..8], twox_64(&key));
+
+	StorageHasher::Identity.hash_into(&key, &mut out[..4]);
+	assert_eq!(out[..4], key);
+}
@@ -1,15 +1,13 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 #![no_builtins]
 
-use embedded_nal::{TcpClient};
-use crate::rpc::{Rpc, RpcError};
-use crate::chain::Chain;
+use crate::rpc::{Rpc, RpcBuffers, RpcError, Clock, ReconnectPolicy, Endpoint, Resolver, BatchRequest, SubscriptionId};
+use crate::chain::{Chain, ChainInfo, RuntimeVersion, Health, Header, JsonHeader};
 use crate::extrinsic::{ExtrinsicPayload, ExtrinsicCalls};
-use crate::account::{Account, AccountError};
+use crate::account::{Account, AccountInfo, AccountError, STORAGE_KEY_HEX_SIZE};
+use crate::storage::{StorageKey, StorageChanges, ChangeSet, QueryParams, MAX_STORAGE_KEY_HEX_SIZE};
+use crate::transport::Transport;
 
-use core::convert::TryFrom;
-use core::str::from_utf8;
-use crate::scale::Compact;
 use crate::calls::Call;
 use crate::calls::transfer::ExtrinsicTransferCall;
 
@@ -23,15 +21,21 @@ pub mod account;
 pub mod chain;
 pub mod calls;
 pub mod extrinsic;
+pub mod hashing;
 pub mod scale;
-mod rpc;
+pub mod rpc;
+pub mod storage;
+pub mod transport;
 
 #[derive(Debug)]
 pub enum ProviderError {
 	RpcError(RpcError),
 	AccountError(AccountError),
 	CannotParse,
-	InvalidSize,
+	/// Buffer is too small, the needed size is returned
+	InvalidSize(usize),
+	/// No subscription has been made, see [`Provider::subscribe_account`](struct.Provider.html#method.subscribe_account)
+	NotSubscribed,
 }
 
 #[derive(Debug)]
@@ -40,6 +44,7 @@ pub enum TcpError {
 	CannotConnect,
 	CannotClose,
 	InvalidAddress,
+	CannotResolve,
 	Unknown,
 }
 
@@ -55,23 +60,28 @@ impl From<AccountError> for ProviderError {
 	}
 }
 
-pub struct Provider<'a, S> {
-	rpc: Rpc<'a, S>,
-	addr: &'a str,
+pub struct Provider<'a, T: Transport> {
+	rpc: Rpc<'a, T>,
+	endpoint: Endpoint<'a>,
 	genesis: Option<[u8; 32]>,
+	/// Subscription to the account info changes, with the websocket session it has been made on
+	subscription: Option<(SubscriptionId, usize)>,
 }
 
-impl<'a, S> Provider<'a, S>
+impl<'a, T: Transport> Provider<'a, T>
 {
 	/// Creates a provider to connect to a remote Substrate chain.
-	/// * Can use any TCP stack implementing [`embedded_nal::TcpClient`](../embedded_nal/trait.TcpClient.html) trait with socket of type `S`.
-	/// * Remote address should respect the format: `IP:port`.
+	/// * Can use any [`Transport`](transport/trait.Transport.html): a TCP stack implementing [`embedded_nal::TcpClient`](../embedded_nal/trait.TcpClient.html)
+	/// wrapped into a [`TcpTransport`](transport/struct.TcpTransport.html), TLS on top of it for `wss://` endpoints,
+	/// or any other link (UART bridge, modem...) implementing the trait.
+	/// * Remote `endpoint` gives the address of the node and the websocket handshake parameters, see [`Endpoint`](rpc/struct.Endpoint.html).
+	/// * Requests and responses are stored into the `buffers` provided by the user, see [`RpcBuffers`](rpc/struct.RpcBuffers.html).
 	/// * A connection attempt is performed but doesn't yield an error if it fails. Attempts will be made when needed.
 	/// ## Errors
 	/// * [`ProviderError`](enum.ProviderError.html) returns an [`RpcError`](enum.ProviderError.html#variant.RpcError) if RPC service is not created.
-	pub fn new(tcp: &'a dyn TcpClient<TcpSocket=S, Error=TcpError>, addr: &'a str) -> Result<Provider<'a, S>, ProviderError> {
-		let mut rpc:Rpc<S>;
-		match Rpc::new(tcp) {
+	pub fn new(transport: T, endpoint: Endpoint<'a>, buffers: RpcBuffers<'a>) -> Result<Provider<'a, T>, ProviderError> {
+		let mut rpc:Rpc<T>;
+		match Rpc::new(transport, buffers) {
 			Ok(r) => {
 				rpc = r;
 			}
@@ -80,69 +90,309 @@ impl<'a, S> Provider<'a, S>
 			}
 		}
 
-		// try to connect without taking into account if it fails
-		let _ = rpc.connect(addr);
+		// try to connect without taking into account if it fails,
+		// HTTP connections are only opened when sending requests
+		if !endpoint.is_http() {
+			let _ = rpc.connect(&endpoint);
+		}
 
 		Ok(Provider {
 			rpc,
-			addr,
+			endpoint,
 			genesis: None,
+			subscription: None,
 		})
 	}
 
-	pub fn system_version(&mut self) -> Result<&str, ProviderError> {
-		if !self.rpc.is_connected() {
-			self.rpc.connect(self.addr)?;
-		}
+	/// Sets the `clock` used to poll non-blocking TCP stacks: operations returning `WouldBlock` are
+	/// retried during `timeout_ms` at most, before returning [`RpcError::Timeout`](rpc/enum.RpcError.html#variant.Timeout).
+	pub fn set_clock(&mut self, clock: &'a dyn Clock, timeout_ms: u64) {
+		self.rpc.set_clock(clock, timeout_ms);
+	}
+
+	/// Sets the `resolver` used to connect to a node given by hostname (e.g. `kusama-rpc.example:443`),
+	/// any TCP stack implementing [`embedded_nal::Dns`](../embedded_nal/trait.Dns.html) can be used.
+	/// As the connection attempt made by [`new`](#method.new) fails without resolver, the
+	/// connection will be established on the first request.
+	pub fn set_resolver(&mut self, resolver: &'a dyn Resolver) {
+		self.rpc.set_resolver(resolver);
+	}
+
+	/// Returns the last error returned by the transport, when a request fails with
+	/// [`RpcError::Transport`](rpc/enum.RpcError.html#variant.Transport)
+	pub fn transport_error(&self) -> Option<&T::Error> {
+		self.rpc.transport_error()
+	}
 
-		let res = self.rpc.rpc_method::<Option<()>>(Some("system_version"), None)?;
+	/// Sets the policy used to recover from a lost connection (e.g. on flaky cellular links):
+	/// the connection is established again and idempotent requests (all but extrinsic submissions)
+	/// are sent again, see [`ReconnectPolicy`](rpc/struct.ReconnectPolicy.html).
+	pub fn set_reconnect_policy(&mut self, policy: ReconnectPolicy) {
+		self.rpc.set_reconnect_policy(policy);
+	}
+
+	pub fn system_version(&mut self) -> Result<&str, ProviderError> {
+		let res = self.rpc.call::<Option<()>, &str>(&self.endpoint, true, Some("system_version"), None)?;
 		Ok(res)
 	}
 
 	pub fn system_name(&mut self) -> Result<&str, ProviderError> {
-		if !self.rpc.is_connected() {
-			self.rpc.connect(self.addr)?;
-		}
+		let res = self.rpc.call::<Option<()>, &str>(&self.endpoint, true, Some("system_name"), None)?;
+		Ok(res)
+	}
 
-		let res = self.rpc.rpc_method::<Option<()>>(Some("system_name"), None)?;
+	/// Peers and sync state of the node
+	pub fn system_health(&mut self) -> Result<Health, ProviderError> {
+		let res = self.rpc.call::<Option<()>, Health>(&self.endpoint, true, Some("system_health"), None)?;
 		Ok(res)
 	}
 
-	pub fn runtime_version(&mut self) -> Result<&str, ProviderError> {
-		if !self.rpc.is_connected() {
-			self.rpc.connect(self.addr)?;
+	/// Next nonce of `account`, taking into account the transactions in the pool of the node
+	pub fn account_next_index(&mut self, account: &Account) -> Result<u32, ProviderError> {
+		let address = account.ss58();
+		let res = self.rpc.call::<_, u32>(&self.endpoint, true, Some("system_accountNextIndex"), Some([address.as_str()]))?;
+		Ok(res)
+	}
+
+	/// Reads the storage entry at `key` (SCALE-encoded value as an hex string),
+	/// `None` if the storage is empty, e.g. for an account which doesn't exist.
+	/// The state of the block `at` is read if given (e.g. a finalized block), otherwise the latest state.
+	///
+	/// ## Errors
+	/// * `RpcError::*`: Error sending the RPC request `state_getStorage`, e.g. when the state of
+	/// block `at` has been pruned by the node
+	pub fn get_storage(&mut self, key: &StorageKey, at: Option<&[u8; 32]>) -> Result<Option<&str>, ProviderError> {
+		let mut key_hex = [0_u8; MAX_STORAGE_KEY_HEX_SIZE];
+		let mut at_hex = [0_u8; HASH_HEX_SIZE];
+		let mut params = [key.write_hex(&mut key_hex)?, ""];
+		let count = match at {
+			Some(hash) => {
+				params[1] = encode_hash(hash, &mut at_hex);
+				2
+			}
+			None => 1,
+		};
+
+		let res = self.rpc.call::<_, Option<&str>>(&self.endpoint, true, Some("state_getStorage"), Some(&params[..count]))?;
+		Ok(res)
+	}
+
+	/// Reads the storage entries at `keys` in one request, e.g. the account info of several accounts,
+	/// at block `at` if given, otherwise at the latest block. The values can be found by key in the
+	/// returned [`StorageChanges`](storage/struct.StorageChanges.html).
+	///
+	/// ## Errors
+	/// * `RpcError::*`: Error sending the RPC request `state_queryStorageAt`, e.g. when the response
+	/// doesn't fit into the buffers
+	pub fn query_storage_at(&mut self, keys: &[StorageKey], at: Option<&[u8; 32]>) -> Result<StorageChanges, ProviderError> {
+		let mut at_hex = [0_u8; HASH_HEX_SIZE];
+		let params = QueryParams {
+			keys,
+			at: at.map(|hash| encode_hash(hash, &mut at_hex)),
+		};
+
+		let res = self.rpc.call::<_, StorageChanges>(&self.endpoint, true, Some("state_queryStorageAt"), Some(params))?;
+		Ok(res)
+	}
+
+	/// Subscribes to the changes of the account info of `account` (`state_subscribeStorage`),
+	/// e.g. to detect incoming payments. Updates are then received with
+	/// [`poll_account`](#method.poll_account) or [`watch_account`](#method.watch_account).
+	/// Any previous subscription is cancelled. Needs a `ws`/`wss` endpoint.
+	///
+	/// ## Errors
+	/// * `RpcError::SubscriptionNotSupported`: the endpoint is an `http`/`https` endpoint
+	/// * `RpcError::*`: Error sending the RPC request `state_subscribeStorage`.
+	pub fn subscribe_account(&mut self, account: &Account) -> Result<(), ProviderError> {
+		let _ = self.unsubscribe_account();
+		self.subscribe(account)
+	}
+
+	/// Subscribes to the account info changes, the previous subscription being replaced on success
+	fn subscribe(&mut self, account: &Account) -> Result<(), ProviderError> {
+		let mut key_hex = [0_u8; MAX_STORAGE_KEY_HEX_SIZE];
+		let keys = [account.storage_key().write_hex(&mut key_hex)?];
+		let id = self.rpc.subscribe(&self.endpoint, "state_subscribeStorage", Some([keys]))?;
+		self.subscription.replace((id, self.rpc.connections()));
+
+		Ok(())
+	}
+
+	/// Cancels the subscription made with [`subscribe_account`](#method.subscribe_account), if any
+	///
+	/// ## Errors
+	/// * `RpcError::*`: Error sending the RPC request `state_unsubscribeStorage`.
+	pub fn unsubscribe_account(&mut self) -> Result<(), ProviderError> {
+		if let Some((id, _)) = self.subscription.take() {
+			self.rpc.unsubscribe(&self.endpoint, "state_unsubscribeStorage", &id)?;
+		}
+
+		Ok(())
+	}
+
+	/// Waits for the next update of the account info of `account`, the first one being sent by the
+	/// node right after subscribing. The update is kept as the last known account info.
+	/// Returns `None` if nothing has been received before the timeout set with
+	/// [`set_clock`](#method.set_clock) (right away without clock if the transport would block).
+	///
+	/// Notifications received while waiting for the response to another request are dropped: the
+	/// account info is then fetched again and returned as an update, or the error if it cannot be.
+	///
+	/// The subscription is lost with the connection, e.g. when another request reconnects: it's
+	/// then made again, before waiting for the update the node sends right after subscribing.
+	///
+	/// ## Errors
+	/// * `NotSubscribed`: [`subscribe_account`](#method.subscribe_account) hasn't been called
+	/// * `RpcError::*`: Error subscribing again, reading the account info after dropped
+	/// notifications, or receiving the notification. The subscription is made again on the next
+	/// call if the connection has been closed.
+	/// * `AccountError::*`: the account info cannot be decoded
+	pub fn poll_account(&mut self, account: &mut Account) -> Result<Option<AccountInfo>, ProviderError> {
+		let connection = match &self.subscription {
+			Some((_, connection)) => *connection,
+			None => return Err(ProviderError::NotSubscribed),
+		};
+		if connection != self.rpc.connections() || !self.rpc.is_connected() {
+			// the node doesn't know the subscription anymore, no need to cancel it, and the update
+			// sent after subscribing again replaces the dropped ones
+			let _ = self.rpc.take_dropped_notifications();
+			self.subscribe(account)?;
+		} else if self.rpc.take_dropped_notifications() {
+			// unlike `get_info`, the last known info must not be returned as an update on errors
+			let res = self.get_storage(&account.storage_key(), None)?;
+			return Ok(Some(account.update_info(res)?))
 		}
 
-		// response:
-		// {"jsonrpc":"2.0","result":{"apis":[["0xdf6acb689907609b",3],["0x37e397fc7c91f5e4",1],["0x40fe3ad401f8959a",4],["0xd2bc9897eed08f15",2],["0xf78b278be53f454c",2],["0xdd718d5cc53262d4",1],["0xab3c0572291feb8b",1],["0xed99c5acb25eedf5",2]],"authoringVersion":1,"implName":"node-template","implVersion":1,"specName":"node-template","specVersion":1,"transactionVersion":1},"id":3}
-		let res = self.rpc.rpc_method::<Option<()>>(Some("state_getRuntimeVersion"), None)?;
+		let (id, _) = self.subscription.as_ref().ok_or(ProviderError::NotSubscribed)?;
+		let changes: StorageChanges = match self.rpc.next_notification::<ChangeSet>("state_storage", id)? {
+			Some(set) => set.into(),
+			None => return Ok(None),
+		};
+
+		match changes.find(&account.storage_key()) {
+			Some(change) => Ok(Some(account.update_info(change.value)?)),
+			None => Ok(None),
+		}
+	}
+
+	/// Calls `on_update` with each update of the account info of `account`, until it returns false
+	/// or until no update has been received before the timeout set with [`set_clock`](#method.set_clock).
+	/// Without clock, it returns as soon as the transport would block: call it again later, e.g.
+	/// after a delay, instead of polling in a loop. See [`poll_account`](#method.poll_account).
+	///
+	/// ## Errors
+	/// * any error returned by [`poll_account`](#method.poll_account)
+	pub fn watch_account<F>(&mut self, account: &mut Account, mut on_update: F) -> Result<(), ProviderError>
+		where F: FnMut(&AccountInfo) -> bool {
+		while let Some(info) = self.poll_account(account)? {
+			if !on_update(&info) {
+				break
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Versions of the runtime, the list of runtime APIs is skipped
+	pub fn runtime_version(&mut self) -> Result<RuntimeVersion, ProviderError> {
+		let res = self.rpc.call::<Option<()>, RuntimeVersion>(&self.endpoint, true, Some("state_getRuntimeVersion"), None)?;
 		Ok(res)
 	}
 }
 
-impl<S>  Chain for Provider<'_, S> {
-	type Error = ProviderError;
+/// Size of a block hash encoded as an hex string: "0x" + 32 bytes
+const HASH_HEX_SIZE: usize = 2 + 32 * 2;
 
-	fn get_block_hash(&mut self, number: Option<[usize; 1]>) -> Result<[u8; 32], Self::Error> {
-		if !self.rpc.is_connected() {
-			self.rpc.connect(self.addr)?;
-		}
+/// Encodes a block hash into `buf` as an hex string, to be sent as a parameter
+fn encode_hash<'b>(hash: &[u8; 32], buf: &'b mut [u8; HASH_HEX_SIZE]) -> &'b str {
+	buf[..2].copy_from_slice(b"0x");
+	// sizes always match
+	let _ = hex::encode_to_slice(hash, &mut buf[2..]);
+	core::str::from_utf8(&buf[..]).unwrap_or("")
+}
+
+/// Decodes a block hash returned as an hex string
+fn decode_hash(res: &str) -> Result<[u8; 32], ProviderError> {
+	let mut block_hash = [0_u8; 32];
 
-		let res = self.rpc.rpc_method(Some("chain_getBlockHash"), number)?;
-		let mut block_hash = [0_u8; 32];
+	let hex_data = res.strip_prefix("0x").map_or(
+		res,
+		|v| v
+	);
+
+	if hex::decode_to_slice(
+		hex_data,
+		&mut block_hash as &mut [u8]).is_err() {
+		return Err(ProviderError::CannotParse)
+	}
+
+	Ok(block_hash)
+}
+
+impl<T: Transport> Provider<'_, T> {
+	/// Fetches the chain info in one batch request, along with the account info of `account` if given:
+	/// latest block hash, runtime version, account storage and genesis hash if not known yet.
+	/// The account info of `account` is updated, an error is returned if it cannot be fetched.
+	fn fetch_chain_info(&mut self, account: Option<&mut Account>) -> Result<ChainInfo, ProviderError> {
+		// storage key as JSON array: `["0x..."]`
+		let mut key = [0_u8; STORAGE_KEY_HEX_SIZE + 4];
+		let mut requests = [
+			BatchRequest::new("chain_getBlockHash", None),
+			BatchRequest::new("state_getRuntimeVersion", None),
+			BatchRequest::new("state_getStorage", None),
+			BatchRequest::new("chain_getBlockHash", Some("[0]")),
+		];
+		let mut count = 2;
+
+		let account_index = if let Some(account) = account.as_ref() {
+			key[..2].copy_from_slice(b"[\"");
+			account.storage_key().write_hex(&mut key[2..2+STORAGE_KEY_HEX_SIZE])?;
+			key[2+STORAGE_KEY_HEX_SIZE..].copy_from_slice(b"\"]");
+			requests[count].params = Some(core::str::from_utf8(&key).map_err(|_| ProviderError::CannotParse)?);
+			count += 1;
+			Some(count - 1)
+		} else {
+			None
+		};
+
+		let genesis_index = if self.genesis.is_none() {
+			requests[count] = requests[3];
+			count += 1;
+			Some(count - 1)
+		} else {
+			None
+		};
 
-		let hex_data = res.strip_prefix("0x").map_or(
-			res,
-			|v| v
-		);
+		let batch = self.rpc.call_batch(&self.endpoint, true, &requests[..count])?;
 
-		if hex::decode_to_slice(
-			hex_data,
-			&mut block_hash as &mut [u8]).is_err() {
-			return Err(ProviderError::CannotParse)
+		let block_hash = decode_hash(batch.result(0)?)?;
+		let runtime: RuntimeVersion = batch.result(1)?;
+		if let (Some(account), Some(i)) = (account, account_index) {
+			// the nonce of the extrinsic is read from the account info, which must be up to date
+			account.update_info(batch.result(i)?)?;
 		}
+		let genesis = match (self.genesis, genesis_index) {
+			(Some(genesis), _) => genesis,
+			(None, Some(i)) => decode_hash(batch.result(i)?)?,
+			(None, None) => return Err(ProviderError::CannotParse),
+		};
+		self.genesis.replace(genesis);
 
-		Ok(block_hash)
+		Ok(ChainInfo {
+			genesis,
+			block_hash,
+			spec_version: runtime.spec_version,
+			transaction_version: runtime.transaction_version,
+		})
+	}
+}
+
+impl<T: Transport> Chain for Provider<'_, T> {
+	type Error = ProviderError;
+
+	fn get_block_hash(&mut self, number: Option<[usize; 1]>) -> Result<[u8; 32], Self::Error> {
+		let res = self.rpc.call::<_, &str>(&self.endpoint, true, Some("chain_getBlockHash"), number)?;
+		decode_hash(res)
 	}
 
 	fn get_genesis_block_hash(&mut self) -> Result<[u8; 32], Self::Error> {
@@ -162,97 +412,92 @@ impl<S>  Chain for Provider<'_, S> {
 		self.genesis.ok_or(ProviderError::CannotParse)
 	}
 
-	fn get_finalized_head(&mut self) -> Result<&str, Self::Error> {
-		if !self.rpc.is_connected() {
-			self.rpc.connect(self.addr)?;
-		}
-
-		let res = self.rpc.rpc_method::<Option<()>>(Some("chain_getFinalizedHead"), None)?;
-		Ok(res)
+	fn get_finalized_head(&mut self) -> Result<[u8; 32], Self::Error> {
+		let res = self.rpc.call::<Option<()>, &str>(&self.endpoint, true, Some("chain_getFinalizedHead"), None)?;
+		decode_hash(res)
 	}
-}
 
+	fn get_header(&mut self, hash: Option<&[u8; 32]>) -> Result<Header, Self::Error> {
+		let mut hash_hex = [0_u8; HASH_HEX_SIZE];
+		let params = hash.map(|hash| [encode_hash(hash, &mut hash_hex)]);
+
+		let res = self.rpc.call::<_, JsonHeader>(&self.endpoint, true, Some("chain_getHeader"), params)?;
+		Header::decode(&res)
+	}
 
-/// Methods param has a few fields
-/// | header (= "0x" + payload size) | payload (extrinsic struct) |
-/// `header` contains string "0x" followed by `payload` size as SCALE encoded hex string
+	fn get_block_number(&mut self) -> Result<u32, Self::Error> {
+		Ok(self.get_header(None)?.number)
+	}
 
-/// Maximum header size
-const MAXIMUM_HEADER_SIZE_BYTES: usize = 8;
+	/// Genesis hash (if not known yet), latest block hash and runtime versions are fetched in one batch request
+	fn get_chain_info(&mut self) -> Result<ChainInfo, Self::Error> {
+		self.fetch_chain_info(None)
+	}
+}
 
-/// Maximum payload size
-const MAXIMUM_PAYLOAD_SIZE_BYTES_ASCII: usize = 504;
-const MAXIMUM_PAYLOAD_SIZE_BYTES: usize = 504/2;
 
-/// Maximum method's param size in bytes
-const MAXIMUM_PARAM_SIZE_BYTES: usize = MAXIMUM_HEADER_SIZE_BYTES + MAXIMUM_PAYLOAD_SIZE_BYTES_ASCII;
+/// Default size of the buffer used to serialize extrinsics into hex strings: "0x" + SCALE compact
+/// length + extrinsic, see `submit_extrinsic_with_buffer` and `submit_raw_extrinsic_with_buffer` to
+/// use a larger buffer.
+const DEFAULT_EXTRINSIC_HEX_SIZE_BYTES: usize = 512;
 
 
-impl<S> ExtrinsicCalls for Provider<'_, S> {
+impl<T: Transport> ExtrinsicCalls for Provider<'_, T> {
 	type Error = ProviderError;
 
-	/// This function is trying to be as memory-efficient as possible by using only one buffer
-	/// to get the payload and translating it in hex characters
-	/// The size of this buffer is `MAXIMUM_PARAM_SIZE_BYTES`
+	/// Builds, signs and submits an extrinsic, serialized into a buffer of
+	/// `DEFAULT_EXTRINSIC_HEX_SIZE_BYTES` allocated on the stack.
 	///
 	/// ## Errors
-	/// * `AccountError::*`: Impossible to fetch source account info
-	/// * `InvalidSize`: Error with buffer size and payload size (buffer isn't large enough?)
-	/// * `RpcError::*`: Error sending the RPC request `author_submitExtrinsic`.
+	/// See [`submit_extrinsic_with_buffer`](#method.submit_extrinsic_with_buffer)
 	fn submit_extrinsic(&mut self, author: &mut Account, method: &dyn Call) -> Result<&str, Self::Error> {
-		let nonce;
-		if let Ok(n) = author.get_nonce(self) {
-			nonce = n;
-		} else {
-			nonce = 0;
-		}
-
-		let extrinsic = ExtrinsicPayload::new(self, method, nonce)?;
-
-		let mut param_buf = [0_u8; MAXIMUM_PARAM_SIZE_BYTES];
-		param_buf[0] = 0x30; // "0"
-		param_buf[1] = 0x78; // "x"
-
-		let payload_size;
-		{
-			let mut sig_payload:&mut [u8; MAXIMUM_PAYLOAD_SIZE_BYTES] =
-				<&mut [u8; MAXIMUM_PAYLOAD_SIZE_BYTES]>::try_from(&mut param_buf[MAXIMUM_HEADER_SIZE_BYTES..MAXIMUM_HEADER_SIZE_BYTES+MAXIMUM_PAYLOAD_SIZE_BYTES]).unwrap();
+		let mut buf = [0_u8; DEFAULT_EXTRINSIC_HEX_SIZE_BYTES];
+		self.submit_extrinsic_with_buffer(author, method, &mut buf)
+	}
 
-			payload_size = extrinsic.signed_tx(author, &mut sig_payload);
-		}
+	/// This function is trying to be as memory-efficient as possible by using only one buffer
+	/// to get the payload and translating it in hex characters.
+	/// The nonce of `author` and the chain info are fetched in one batch request.
+	/// `buf` can be of any size, large enough to hold the extrinsic as an hex string.
+	///
+	/// ## Errors
+	/// * `CannotParse`: chain info returned by the node cannot be parsed
+	/// * `AccountError`: account info of `author` returned by the node cannot be decoded
+	/// * `InvalidSize`: `buf` isn't large enough, the needed size is returned
+	/// * `RpcError::*`: Error fetching the chain info or the account info of `author` (nothing is
+	/// submitted then, as the nonce would be outdated), or sending the RPC request `author_submitExtrinsic`.
+	fn submit_extrinsic_with_buffer(&mut self, author: &mut Account, method: &dyn Call, buf: &mut [u8]) -> Result<&str, Self::Error> {
+		let info = self.fetch_chain_info(Some(author))?;
+
+		let extrinsic = ExtrinsicPayload::from_chain_info(method, author.last_nonce(), &info);
+		let param = extrinsic.signed_tx_hex(author, buf)?;
+
+		let res = self.rpc.call::<_, &str>(&self.endpoint, false, Some("author_submitExtrinsic"), Some([param]))?;
+		Ok(res)
+	}
 
-		if payload_size != 0_usize {
-			// append payload size as a header (scale compacted)
-			let size_u32 = payload_size as u32;
-			let mut buf = [0_u8; MAXIMUM_HEADER_SIZE_BYTES-2];
-			let size_header_length = size_u32.scale_compact(&mut buf);
-
-			let header:&mut [u8; MAXIMUM_HEADER_SIZE_BYTES] = <&mut [u8; MAXIMUM_HEADER_SIZE_BYTES]>::try_from(&mut param_buf[..MAXIMUM_HEADER_SIZE_BYTES]).unwrap();
-			match size_header_length {
-				2 => {
-					let size_bytes: &mut[u8; 2] = <&mut [u8; 2]>::try_from(&mut buf[..2]).unwrap();
-					hex::encode_to_slice::<[u8; 2]>(*size_bytes, &mut header[MAXIMUM_HEADER_SIZE_BYTES-4..MAXIMUM_HEADER_SIZE_BYTES].as_mut()).unwrap();
-				}
-				_ => {}
-			}
+	/// Submits an extrinsic which has been built and signed elsewhere (e.g. by an offline device),
+	/// serialized into a buffer of `DEFAULT_EXTRINSIC_HEX_SIZE_BYTES` allocated on the stack.
+	///
+	/// ## Errors
+	/// See [`submit_raw_extrinsic_with_buffer`](#method.submit_raw_extrinsic_with_buffer)
+	fn submit_raw_extrinsic(&mut self, extrinsic: &[u8]) -> Result<&str, Self::Error> {
+		let mut buf = [0_u8; DEFAULT_EXTRINSIC_HEX_SIZE_BYTES];
+		self.submit_raw_extrinsic_with_buffer(extrinsic, &mut buf)
+	}
 
-			{
-				let sig_payload:&mut [u8; MAXIMUM_PAYLOAD_SIZE_BYTES] = <&mut [u8; MAXIMUM_PAYLOAD_SIZE_BYTES]>::try_from(&mut param_buf[MAXIMUM_HEADER_SIZE_BYTES..MAXIMUM_HEADER_SIZE_BYTES+ MAXIMUM_PAYLOAD_SIZE_BYTES]).unwrap();
-				hex::encode_to_slice::<[u8; MAXIMUM_PAYLOAD_SIZE_BYTES_ASCII /2]>(*sig_payload, &mut param_buf[MAXIMUM_HEADER_SIZE_BYTES..]).unwrap();
-			}
+	/// Submits an extrinsic which has been built and signed elsewhere (e.g. by an offline device).
+	/// `extrinsic` is the encoded signed extrinsic, without its length prefix: the SCALE compact
+	/// length is added before sending it as an hex string written into `buf`.
+	///
+	/// ## Errors
+	/// * `InvalidSize`: `buf` isn't large enough, the needed size is returned
+	/// * `RpcError::*`: Error sending the RPC request `author_submitExtrinsic`.
+	fn submit_raw_extrinsic_with_buffer(&mut self, extrinsic: &[u8], buf: &mut [u8]) -> Result<&str, Self::Error> {
+		let param = extrinsic::encode_hex(extrinsic, buf)?;
 
-			let res;
-			{
-				let start_idx = MAXIMUM_HEADER_SIZE_BYTES-size_header_length*2-2;
-				param_buf[start_idx] = 0x30; // "0"
-				param_buf[start_idx+1] = 0x78; // "x"
-				let sig_payload_str:&str = from_utf8(param_buf[start_idx..MAXIMUM_HEADER_SIZE_BYTES+payload_size*2].as_ref()).unwrap();
-				res = self.rpc.rpc_method(Some("author_submitExtrinsic"), Some([sig_payload_str]))?;
-			}
-			Ok(res)
-		} else {
-			Err(ProviderError::InvalidSize)
-		}
+		let res = self.rpc.call::<_, &str>(&self.endpoint, false, Some("author_submitExtrinsic"), Some([param]))?;
+		Ok(res)
 	}
 
 	/// This function creates the Call object to transfer balance between author and `dest_account`
@@ -0,0 +1,150 @@
+use core::fmt::Write;
+use serde::Deserialize;
+use crate::rpc::{RpcError, JsonError, JsonId, SliceWriter, parse_result};
+
+/// Request sent in a batch with [`Rpc::call_batch`](struct.Rpc.html#method.call_batch).
+/// `params` are already serialized as a JSON array, e.g. `["0x1234"]` or `[0]`.
+#[derive(Debug, Clone, Copy)]
+pub struct BatchRequest<'r> {
+	pub method: &'r str,
+	/// Written as is into the request: must be valid JSON, quotes and backslashes in strings
+	/// being escaped
+	pub params: Option<&'r str>,
+}
+
+impl<'r> BatchRequest<'r> {
+	/// `params` must be a valid JSON array, they are not checked nor escaped: invalid params make
+	/// the node reject the whole batch
+	pub fn new(method: &'r str, params: Option<&'r str>) -> BatchRequest<'r> {
+		BatchRequest {
+			method,
+			params,
+		}
+	}
+}
+
+/// Responses to a batch request, which can be received in any order
+pub struct BatchResponse<'b> {
+	response: &'b str,
+	first_id: usize,
+	len: usize,
+}
+
+impl<'b> BatchResponse<'b> {
+	pub(crate) fn new(response: &'b str, first_id: usize, len: usize) -> BatchResponse<'b> {
+		BatchResponse {
+			response,
+			first_id,
+			len,
+		}
+	}
+
+	/// Returns the result of the request at `index` in the batch, deserialized as `R` as with
+	/// [`Rpc::rpc_method`](struct.Rpc.html#method.rpc_method).
+	///
+	/// # Errors
+	/// * `ResponseDoesNotMatch`: no response to this request has been found
+	/// * `Json(ErrorParsing)`: the result cannot be deserialized as `R`
+	/// * any error returned by the node for this request
+	pub fn result<R: Deserialize<'b>>(&self, index: usize) -> Result<R, RpcError> {
+		if index >= self.len {
+			return Err(RpcError::ResponseDoesNotMatch)
+		}
+
+		let id = self.first_id + index;
+		let response = JsonObjects::new(self.response)
+			.find(|obj| matches!(serde_json_core::from_str::<JsonId>(obj), Ok((json, _)) if json.id == Some(id)))
+			.ok_or(RpcError::ResponseDoesNotMatch)?;
+
+		parse_result(response, id)
+	}
+}
+
+/// Serializes `requests` into `buf` as a JSON array, with ids starting at `first_id`.
+/// Returns the size of the serialized batch.
+///
+/// # Errors
+/// * `Json(ErrorSerializing)`: the requests don't fit into `buf`
+pub(crate) fn serialize(requests: &[BatchRequest], first_id: usize, buf: &mut [u8]) -> Result<usize, RpcError> {
+	let mut writer = SliceWriter::new(buf);
+	write_batch(&mut writer, requests, first_id)
+		.map_err(|_| RpcError::Json(JsonError::ErrorSerializing))?;
+
+	Ok(writer.len())
+}
+
+fn write_batch(writer: &mut SliceWriter, requests: &[BatchRequest], first_id: usize) -> core::fmt::Result {
+	writer.write_char('[')?;
+	for (i, request) in requests.iter().enumerate() {
+		if i > 0 {
+			writer.write_char(',')?;
+		}
+		write!(writer, "{{\"id\":{},\"jsonrpc\":\"2.0\",\"method\":\"{}\"", first_id + i, request.method)?;
+		if let Some(params) = request.params {
+			write!(writer, ",\"params\":{}", params)?;
+		}
+		writer.write_char('}')?;
+	}
+	writer.write_char(']')
+}
+
+/// Iterates over the objects of a JSON array without parsing them,
+/// only braces outside of strings are taken into account.
+pub(crate) struct JsonObjects<'s> {
+	s: &'s str,
+	pos: usize,
+}
+
+impl<'s> JsonObjects<'s> {
+	pub(crate) fn new(s: &'s str) -> JsonObjects<'s> {
+		JsonObjects {
+			s,
+			pos: 0,
+		}
+	}
+}
+
+impl<'s> Iterator for JsonObjects<'s> {
+	type Item = &'s str;
+
+	fn next(&mut self) -> Option<&'s str> {
+		let mut depth = 0_usize;
+		let mut start = 0_usize;
+		let mut in_string = false;
+		let mut escaped = false;
+
+		for (i, c) in self.s.bytes().enumerate().skip(self.pos) {
+			if in_string {
+				if escaped {
+					escaped = false;
+				} else if c == b'\\' {
+					escaped = true;
+				} else if c == b'"' {
+					in_string = false;
+				}
+				continue;
+			}
+
+			match c {
+				b'"' => in_string = true,
+				b'{' => {
+					if depth == 0 {
+						start = i;
+					}
+					depth += 1;
+				}
+				b'}' if depth > 0 => {
+					depth -= 1;
+					if depth == 0 {
+						self.pos = i + 1;
+						return Some(&self.s[start..=i])
+					}
+				}
+				_ => {}
+			}
+		}
+
+		self.pos = self.s.len();
+		None
+	}
+}
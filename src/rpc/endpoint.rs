@@ -0,0 +1,144 @@
+use core::fmt::Write;
+use heapless::{String, consts::*};
+use crate::TcpError;
+use crate::rpc::RpcError;
+
+/// Protocol used to reach the node
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scheme {
+	/// Websocket over plain TCP: `ws://`
+	Ws,
+	/// Websocket over TLS: `wss://`, needs a TLS transport (e.g. `TlsTransport` with the `tls` feature)
+	Wss,
+	/// One HTTP/1.1 POST request per connection: `http://`
+	Http,
+	/// Same as `Http`, over TLS: `https://`
+	Https,
+}
+
+impl Scheme {
+	fn default_port(self) -> u16 {
+		match self {
+			Scheme::Ws | Scheme::Http => 80,
+			Scheme::Wss | Scheme::Https => 443,
+		}
+	}
+}
+
+/// Node endpoint: address used for the TCP connection along with the parameters of the
+/// websocket opening handshake, so that nodes behind reverse proxies or hosted nodes
+/// (with paths like `/ws` or API keys in the path) can be reached.
+/// For HTTP endpoints, `path` and `headers` are used by each POST request.
+#[derive(Debug, Clone, Copy)]
+pub struct Endpoint<'a> {
+	pub scheme: Scheme,
+	pub host: &'a str,
+	pub port: u16,
+	/// Path of the websocket, starting with `/`
+	pub path: &'a str,
+	/// `Origin` header, `http://host:port` (`https://` for `wss`) is used if not set
+	pub origin: Option<&'a str>,
+	/// Additional headers sent during the handshake, formatted as `Name: value`
+	pub headers: Option<&'a [&'a str]>,
+}
+
+impl<'a> Endpoint<'a> {
+	pub fn new(host: &'a str, port: u16) -> Endpoint<'a> {
+		Endpoint {
+			scheme: Scheme::Ws,
+			host,
+			port,
+			path: "/",
+			origin: None,
+			headers: None,
+		}
+	}
+
+	/// Parses an URL with the format `[scheme://]host[:port][/path]`, `scheme` being `ws` (default), `wss`,
+	/// `http` or `https`, `host` being an IP address or a
+	/// hostname (resolved when connecting, see [`Resolver`](trait.Resolver.html)).
+	/// IPv6 addresses must be enclosed in brackets: `[::1]:9944`.
+	/// Default port is 80, or 443 for `wss` and `https`.
+	///
+	/// # Errors
+	/// * `TcpError::InvalidAddress`: URL cannot be parsed
+	pub fn parse(url: &'a str) -> Result<Endpoint<'a>, RpcError> {
+		let (scheme, rest) = if let Some(rest) = url.strip_prefix("wss://") {
+			(Scheme::Wss, rest)
+		} else if let Some(rest) = url.strip_prefix("https://") {
+			(Scheme::Https, rest)
+		} else if let Some(rest) = url.strip_prefix("http://") {
+			(Scheme::Http, rest)
+		} else {
+			(Scheme::Ws, url.strip_prefix("ws://").unwrap_or(url))
+		};
+
+		let (authority, path) = match rest.find('/') {
+			Some(i) => (&rest[..i], &rest[i..]),
+			None => (rest, "/"),
+		};
+
+		let (host, port) = if let Some(bracketed) = authority.strip_prefix('[') {
+			let end = bracketed.find(']').ok_or(RpcError::TcpSocket(TcpError::InvalidAddress))?;
+			(&bracketed[..end], bracketed[end+1..].strip_prefix(':').unwrap_or(""))
+		} else {
+			match authority.rfind(':') {
+				Some(i) => (&authority[..i], &authority[i+1..]),
+				None => (authority, ""),
+			}
+		};
+
+		if host.is_empty() {
+			return Err(RpcError::TcpSocket(TcpError::InvalidAddress))
+		}
+
+		let port = if port.is_empty() {
+			scheme.default_port()
+		} else {
+			port.parse::<u16>().map_err(|_| RpcError::TcpSocket(TcpError::InvalidAddress))?
+		};
+
+		Ok(Endpoint {
+			scheme,
+			path,
+			..Endpoint::new(host, port)
+		})
+	}
+
+	/// Returns true if requests are sent over HTTP instead of websocket
+	pub(crate) fn is_http(&self) -> bool {
+		matches!(self.scheme, Scheme::Http | Scheme::Https)
+	}
+
+	/// Value of the `Host` header: `host:port`
+	pub(crate) fn host_header(&self) -> Result<String<U128>, RpcError> {
+		let mut header = String::new();
+		let res = if self.host.contains(':') {
+			// IPv6
+			write!(header, "[{}]:{}", self.host, self.port)
+		} else {
+			write!(header, "{}:{}", self.host, self.port)
+		};
+		res.map_err(|_| RpcError::TcpSocket(TcpError::InvalidAddress))?;
+
+		Ok(header)
+	}
+
+	/// Value of the `Origin` header, `http[s]://host:port` if not set
+	pub(crate) fn origin_header(&self) -> Result<String<U128>, RpcError> {
+		let mut header = String::new();
+		if let Some(origin) = self.origin {
+			header.push_str(origin)
+				.map_err(|_| RpcError::TcpSocket(TcpError::InvalidAddress))?;
+		} else {
+			let http = match self.scheme {
+				Scheme::Ws | Scheme::Http => "http",
+				Scheme::Wss | Scheme::Https => "https",
+			};
+			write!(header, "{}://{}", http, self.host_header()?)
+				.map_err(|_| RpcError::TcpSocket(TcpError::InvalidAddress))?;
+		}
+
+		Ok(header)
+	}
+}
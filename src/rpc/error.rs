@@ -0,0 +1,139 @@
+use heapless::{String, consts::*};
+
+/// Error object returned by the node, with the message and data truncated to 64 bytes
+#[derive(Debug, Clone)]
+pub struct NodeError {
+	pub code: i64,
+	pub message: String<U64>,
+	/// Details on the error, if given as a string
+	pub data: Option<String<U64>>,
+}
+
+/// Errors returned by `author_*` methods (e.g. `author_submitExtrinsic`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuthorError {
+	/// Extrinsic has an invalid format
+	BadFormat,
+	/// Extrinsic verification failed
+	Verification,
+	InvalidTransaction(InvalidTransaction),
+	/// Transaction validity cannot be determined
+	UnknownValidity,
+	/// Transaction has been banned from the pool for a while
+	TemporarilyBanned,
+	/// Same transaction is already in the pool
+	AlreadyImported,
+	/// Another transaction with the same nonce and a higher priority is in the pool
+	TooLowPriority,
+	/// Transactions depend on each other
+	CycleDetected,
+	/// Transaction pool is full, the transaction has been dropped
+	ImmediatelyDropped,
+}
+
+/// Reasons for a transaction to be invalid, as reported by the runtime
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InvalidTransaction {
+	/// Call of the transaction is not expected
+	Call,
+	/// Inability to pay some fees (e.g. account balance too low)
+	Payment,
+	/// Transaction will be valid in the future (nonce too high)
+	Future,
+	/// Transaction is outdated (nonce too low)
+	Stale,
+	/// Transaction has a bad signature
+	BadProof,
+	/// Transaction has an ancient birth block (mortal era expired)
+	AncientBirthBlock,
+	/// Transaction would exhaust the block limits
+	ExhaustsResources,
+	/// Custom error of the runtime
+	Custom(u8),
+	BadMandatory,
+	MandatoryDispatch,
+	/// Reason cannot be parsed
+	Unknown,
+}
+
+const BAD_FORMAT: i64 = 1001;
+const VERIFICATION_ERROR: i64 = 1002;
+const POOL_INVALID_TX: i64 = 1010;
+const POOL_UNKNOWN_VALIDITY: i64 = 1011;
+const POOL_TEMPORARILY_BANNED: i64 = 1012;
+const POOL_ALREADY_IMPORTED: i64 = 1013;
+const POOL_TOO_LOW_PRIORITY: i64 = 1014;
+const POOL_CYCLE_DETECTED: i64 = 1015;
+const POOL_IMMEDIATELY_DROPPED: i64 = 1016;
+
+impl NodeError {
+	pub(crate) fn new(code: i64, message: Option<&str>, data: Option<&str>) -> NodeError {
+		NodeError {
+			code,
+			message: truncate(message.unwrap_or("")),
+			data: data.map(truncate),
+		}
+	}
+
+	/// Maps the error to a known `author_*` error, by code then by data for invalid transactions
+	pub fn author_error(&self) -> Option<AuthorError> {
+		let err = match self.code {
+			BAD_FORMAT => AuthorError::BadFormat,
+			VERIFICATION_ERROR => AuthorError::Verification,
+			POOL_INVALID_TX => {
+				let data = self.data.as_ref().map_or("", |d| d.as_str());
+				AuthorError::InvalidTransaction(InvalidTransaction::from_data(data))
+			}
+			POOL_UNKNOWN_VALIDITY => AuthorError::UnknownValidity,
+			POOL_TEMPORARILY_BANNED => AuthorError::TemporarilyBanned,
+			POOL_ALREADY_IMPORTED => AuthorError::AlreadyImported,
+			POOL_TOO_LOW_PRIORITY => AuthorError::TooLowPriority,
+			POOL_CYCLE_DETECTED => AuthorError::CycleDetected,
+			POOL_IMMEDIATELY_DROPPED => AuthorError::ImmediatelyDropped,
+			_ => return None,
+		};
+
+		Some(err)
+	}
+}
+
+impl InvalidTransaction {
+	/// Parses the reason given by Substrate in the error data, e.g. "Inability to pay some fees (e.g. account balance too low)"
+	fn from_data(data: &str) -> InvalidTransaction {
+		const REASONS: [(&str, InvalidTransaction); 9] = [
+			("Transaction call is not expected", InvalidTransaction::Call),
+			("Inability to pay some fees", InvalidTransaction::Payment),
+			("Transaction will be valid in the future", InvalidTransaction::Future),
+			("Transaction is outdated", InvalidTransaction::Stale),
+			("Transaction has a bad signature", InvalidTransaction::BadProof),
+			("Transaction has an ancient birth block", InvalidTransaction::AncientBirthBlock),
+			// "exhausts" in Substrate's message
+			("Transaction would exhaust", InvalidTransaction::ExhaustsResources),
+			("A call was labelled as mandatory", InvalidTransaction::BadMandatory),
+			("Transaction dispatch is mandatory", InvalidTransaction::MandatoryDispatch),
+		];
+
+		// some versions prefix the reason
+		let reason = data.strip_prefix("Invalid Transaction: ").unwrap_or(data);
+
+		if let Some(code) = reason.strip_prefix("Custom error: ") {
+			return code.trim().parse::<u8>().map_or(InvalidTransaction::Unknown, InvalidTransaction::Custom)
+		}
+
+		REASONS.iter()
+			.find(|(prefix, _)| reason.starts_with(prefix))
+			.map_or(InvalidTransaction::Unknown, |(_, invalid)| *invalid)
+	}
+}
+
+/// Copies `s` into a heapless string, truncated on a char boundary if too long
+fn truncate<N: heapless::ArrayLength<u8>>(s: &str) -> String<N> {
+	let mut truncated = String::new();
+	for c in s.chars() {
+		if truncated.push(c).is_err() {
+			break
+		}
+	}
+
+	truncated
+}
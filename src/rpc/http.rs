@@ -0,0 +1,114 @@
+use core::fmt::Write;
+use core::str;
+use crate::rpc::{Endpoint, RpcError, HttpError, SliceWriter};
+
+/// Head of an HTTP response
+pub(crate) struct ResponseHead {
+	pub(crate) status: u16,
+	/// Size of the head, including the empty line, body starts right after
+	pub(crate) len: usize,
+	pub(crate) content_length: Option<usize>,
+	pub(crate) chunked: bool,
+}
+
+/// Writes the request line and headers of a JSON-RPC POST request to `endpoint` into `buf`,
+/// the body of size `body_len` being sent right after. Returns the size of the head.
+/// The connection is closed by the server after the response.
+///
+/// # Errors
+/// * `MessageTooLarge`: the head doesn't fit into `buf`
+pub(crate) fn write_request_head(endpoint: &Endpoint, body_len: usize, buf: &mut [u8]) -> Result<usize, RpcError> {
+	let host = endpoint.host_header()?;
+	let mut writer = SliceWriter::new(buf);
+
+	write!(writer, "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+		endpoint.path, host, body_len)
+		.map_err(|_| RpcError::MessageTooLarge)?;
+	for header in endpoint.headers.unwrap_or(&[]) {
+		write!(writer, "{}\r\n", header).map_err(|_| RpcError::MessageTooLarge)?;
+	}
+	writer.write_str("\r\n").map_err(|_| RpcError::MessageTooLarge)?;
+
+	Ok(writer.len())
+}
+
+fn find_crlf(buf: &[u8]) -> Option<usize> {
+	buf.windows(2).position(|w| w == b"\r\n")
+}
+
+/// Parses the status line and headers of the response, returns `None` if the head has not been
+/// entirely received yet.
+///
+/// # Errors
+/// * `HttpError::InvalidResponse`: the status line or a header cannot be parsed
+pub(crate) fn parse_response_head(buf: &[u8]) -> Result<Option<ResponseHead>, RpcError> {
+	let len = match buf.windows(4).position(|w| w == b"\r\n\r\n") {
+		Some(i) => i + 4,
+		None => return Ok(None),
+	};
+	let head = str::from_utf8(&buf[..len])?;
+	let mut lines = head.split("\r\n");
+
+	// status line: `HTTP/1.1 200 OK`
+	let mut status_line = lines.next().unwrap_or("").split(' ');
+	if !status_line.next().unwrap_or("").starts_with("HTTP/") {
+		return Err(RpcError::Http(HttpError::InvalidResponse))
+	}
+	let status = status_line.next().unwrap_or("").parse::<u16>()
+		.map_err(|_| RpcError::Http(HttpError::InvalidResponse))?;
+
+	let mut content_length = None;
+	let mut chunked = false;
+	for line in lines.filter(|l| !l.is_empty()) {
+		let colon = line.find(':').ok_or(RpcError::Http(HttpError::InvalidResponse))?;
+		let (name, value) = (line[..colon].trim(), line[colon+1..].trim());
+
+		if name.eq_ignore_ascii_case("content-length") {
+			content_length = Some(value.parse::<usize>()
+				.map_err(|_| RpcError::Http(HttpError::InvalidResponse))?);
+		} else if name.eq_ignore_ascii_case("transfer-encoding") {
+			chunked = value.eq_ignore_ascii_case("chunked");
+		}
+	}
+
+	Ok(Some(ResponseHead {
+		status,
+		len,
+		content_length,
+		chunked,
+	}))
+}
+
+/// Decodes in place a body sent with the chunked transfer encoding, returns the size of the decoded body.
+///
+/// # Errors
+/// * `HttpError::InvalidResponse`: chunk sizes cannot be parsed or the body is incomplete
+pub(crate) fn decode_chunked(buf: &mut [u8]) -> Result<usize, RpcError> {
+	let mut read = 0_usize;
+	let mut written = 0_usize;
+
+	loop {
+		// chunk size in hex, optionally followed by extensions
+		let line_end = read + find_crlf(&buf[read..]).ok_or(RpcError::Http(HttpError::InvalidResponse))?;
+		let line = str::from_utf8(&buf[read..line_end])?;
+		let size = usize::from_str_radix(line.split(';').next().unwrap_or("").trim(), 16)
+			.map_err(|_| RpcError::Http(HttpError::InvalidResponse))?;
+		read = line_end + 2;
+
+		if size == 0 {
+			return Ok(written)
+		}
+		if read + size > buf.len() {
+			return Err(RpcError::Http(HttpError::InvalidResponse))
+		}
+
+		// decoded data is never larger than the encoded one
+		buf.copy_within(read..read+size, written);
+		written += size;
+		// skip CRLF after the chunk data
+		read += size + 2;
+		if read > buf.len() {
+			return Err(RpcError::Http(HttpError::InvalidResponse))
+		}
+	}
+}
@@ -0,0 +1,115 @@
+use embedded_nal::{SocketAddr, nb};
+use crate::TcpError;
+use crate::rpc::{Clock, RpcError};
+use crate::transport::Transport;
+
+/// Transport used to reach the node.
+/// Operations which would block are retried until `timeout_ms` expires, using the `clock`.
+pub(crate) struct Link<'a, T: Transport> {
+	transport: T,
+	clock: Option<&'a dyn Clock>,
+	timeout_ms: u64,
+	/// Last error returned by the transport
+	error: Option<T::Error>,
+	/// The last error can be recovered by reconnecting
+	recoverable: bool,
+}
+
+impl<'a, T: Transport> Link<'a, T> {
+	pub(crate) fn new(transport: T) -> Link<'a, T> {
+		Link {
+			transport,
+			clock: None,
+			timeout_ms: 0,
+			error: None,
+			recoverable: false,
+		}
+	}
+
+	pub(crate) fn set_clock(&mut self, clock: &'a dyn Clock, timeout_ms: u64) {
+		self.clock.replace(clock);
+		self.timeout_ms = timeout_ms;
+	}
+
+	pub(crate) fn clock(&self) -> Option<&'a dyn Clock> {
+		self.clock
+	}
+
+	pub(crate) fn error(&self) -> Option<&T::Error> {
+		self.error.as_ref()
+	}
+
+	/// Returns true if the last error returned by the transport can be recovered by reconnecting
+	pub(crate) fn is_recoverable(&self) -> bool {
+		self.recoverable
+	}
+
+	/// Calls `f` on the transport until it doesn't return `WouldBlock`.
+	///
+	/// # Errors
+	/// * `Timeout`: `timeout_ms` elapsed while `f` was still returning `WouldBlock`
+	/// * `Embedded(WouldBlock)`: `f` returned `WouldBlock` and no clock has been set to poll the transport
+	/// * `Transport`: `f` returned an error, kept until the next one
+	fn poll<R, F>(&mut self, mut f: F) -> Result<R, RpcError>
+		where F: FnMut(&mut T) -> nb::Result<R, T::Error> {
+		let start = self.clock.map(|c| c.now_ms());
+
+		loop {
+			match f(&mut self.transport) {
+				Ok(v) => {
+					return Ok(v)
+				}
+				Err(nb::Error::Other(e)) => {
+					self.recoverable = self.transport.is_recoverable(&e);
+					self.error.replace(e);
+					return Err(RpcError::Transport)
+				}
+				Err(nb::Error::WouldBlock) => {
+					match (self.clock, start) {
+						(Some(clock), Some(start)) => {
+							if clock.now_ms().saturating_sub(start) >= self.timeout_ms {
+								return Err(RpcError::Timeout)
+							}
+						}
+						_ => {
+							return Err(RpcError::Embedded(nb::Error::WouldBlock))
+						}
+					}
+				}
+			}
+		}
+	}
+
+	pub(crate) fn connect(&mut self, remote: SocketAddr, hostname: &str) -> Result<(), RpcError> {
+		self.poll(|transport| transport.connect(remote, hostname))
+	}
+
+	/// Sends the whole `data`, even if the transport accepts only part of it at once
+	///
+	/// # Errors
+	/// * `TcpError::CountNotMatching`: the transport doesn't accept any more byte
+	pub(crate) fn send_all(&mut self, data: &[u8]) -> Result<(), RpcError> {
+		let mut sent = 0_usize;
+		while sent < data.len() {
+			let written = self.poll(|transport| transport.send(&data[sent..]))?;
+			if written == 0 {
+				return Err(RpcError::TcpSocket(TcpError::CountNotMatching))
+			}
+			sent += written;
+		}
+
+		Ok(())
+	}
+
+	pub(crate) fn receive(&mut self, buf: &mut [u8]) -> Result<usize, RpcError> {
+		self.poll(|transport| transport.receive(buf))
+	}
+
+	pub(crate) fn is_connected(&self) -> bool {
+		self.transport.is_connected()
+	}
+
+	pub(crate) fn close(&mut self) {
+		self.transport.close();
+	}
+}
@@ -0,0 +1,343 @@
+//! Transport and clock replaying what a node would send, to test the provider without a node
+
+extern crate std;
+
+use std::vec::Vec;
+use std::string::String;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::cell::{Cell, RefCell};
+use std::format;
+use embedded_nal::{SocketAddr, nb};
+use crate::rpc::Clock;
+use crate::transport::Transport;
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct MockError;
+
+/// What the transport returns on the next `receive`
+pub(crate) enum Step {
+	/// Data received, over several reads if it doesn't fit into the buffer
+	Data(Vec<u8>),
+	/// Connection closed by the node
+	Closed,
+}
+
+/// State of the transport, shared with the test
+#[derive(Default)]
+pub(crate) struct MockState {
+	/// Data to be received, `WouldBlock` is returned once everything has been received
+	pub(crate) steps: VecDeque<Step>,
+	/// Websocket handshake responses are received in two reads
+	pub(crate) split_handshake: bool,
+	/// Connections are refused
+	pub(crate) refuse: bool,
+	/// Errors cannot be recovered by reconnecting
+	pub(crate) unrecoverable: bool,
+	/// Websocket handshakes are refused with `403 Forbidden`
+	pub(crate) refuse_handshake: bool,
+	/// Number of connections opened
+	pub(crate) connections: usize,
+	/// Number of connections attempted, refused ones included
+	pub(crate) attempts: usize,
+	/// The connection is open, connecting again fails until it has been closed
+	pub(crate) connected: bool,
+	/// Data sent, as is
+	pub(crate) sent: Vec<u8>,
+	/// Websocket frames sent after the handshake, unmasked: opcode and payload
+	pub(crate) frames: Vec<(u8, Vec<u8>)>,
+	websocket: bool,
+}
+
+impl MockState {
+	pub(crate) fn new() -> Rc<RefCell<MockState>> {
+		Rc::new(RefCell::new(MockState::default()))
+	}
+
+	pub(crate) fn receive(&mut self, step: Step) {
+		self.steps.push_back(step);
+	}
+
+	/// Data sent, as text
+	pub(crate) fn sent_text(&self) -> String {
+		String::from_utf8_lossy(&self.sent).into_owned()
+	}
+
+	/// Text messages sent over websocket
+	pub(crate) fn texts(&self) -> Vec<String> {
+		self.frames.iter()
+			.filter(|(opcode, _)| *opcode == 1)
+			.map(|(_, payload)| String::from_utf8_lossy(payload).into_owned())
+			.collect()
+	}
+
+	/// Answers the websocket handshake request `req`, before any other step
+	fn accept(&mut self, req: &[u8]) {
+		let response = if self.refuse_handshake {
+			b"HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\n\r\n".to_vec()
+		} else {
+			handshake_response(req)
+		};
+
+		if self.split_handshake {
+			let (head, tail) = response.split_at(response.len() / 2);
+			self.steps.push_front(Step::Data(tail.to_vec()));
+			self.steps.push_front(Step::Data(head.to_vec()));
+		} else {
+			self.steps.push_front(Step::Data(response));
+		}
+		self.websocket = true;
+	}
+}
+
+/// Transport receiving the steps of its state, and answering websocket handshakes
+pub(crate) struct MockTransport {
+	state: Rc<RefCell<MockState>>,
+}
+
+impl MockTransport {
+	pub(crate) fn new(state: &Rc<RefCell<MockState>>) -> MockTransport {
+		MockTransport {
+			state: state.clone(),
+		}
+	}
+}
+
+impl Transport for MockTransport {
+	type Error = MockError;
+
+	fn connect(&mut self, _remote: SocketAddr, _hostname: &str) -> nb::Result<(), Self::Error> {
+		let mut state = self.state.borrow_mut();
+		state.attempts += 1;
+		if state.refuse || state.connected {
+			return Err(nb::Error::Other(MockError))
+		}
+		state.connected = true;
+		state.websocket = false;
+		state.connections += 1;
+		Ok(())
+	}
+
+	fn send(&mut self, data: &[u8]) -> nb::Result<usize, Self::Error> {
+		let mut state = self.state.borrow_mut();
+		if !state.connected {
+			return Err(nb::Error::Other(MockError))
+		}
+		state.sent.extend_from_slice(data);
+		if state.websocket {
+			unmask_frames(data, &mut state.frames);
+		} else if data.starts_with(b"GET ") {
+			state.accept(data);
+		}
+		Ok(data.len())
+	}
+
+	fn receive(&mut self, buf: &mut [u8]) -> nb::Result<usize, Self::Error> {
+		let mut state = self.state.borrow_mut();
+		if !state.connected {
+			return Err(nb::Error::Other(MockError))
+		}
+		match state.steps.pop_front() {
+			None => Err(nb::Error::WouldBlock),
+			Some(Step::Closed) => {
+				state.connected = false;
+				Ok(0)
+			}
+			Some(Step::Data(data)) => {
+				let len = buf.len().min(data.len());
+				buf[..len].copy_from_slice(&data[..len]);
+				if len < data.len() {
+					state.steps.push_front(Step::Data(data[len..].to_vec()));
+				}
+				Ok(len)
+			}
+		}
+	}
+
+	fn is_connected(&self) -> bool {
+		self.state.borrow().connected
+	}
+
+	fn close(&mut self) {
+		self.state.borrow_mut().connected = false;
+	}
+
+	fn is_recoverable(&self, _error: &MockError) -> bool {
+		!self.state.borrow().unrecoverable
+	}
+}
+
+/// Declares `$buffers`, the `RpcBuffers` of an `Rpc` allocated on the stack: `$size` bytes each, or
+/// the sizes of `in_buf`, `out_buf` and `req_buf`
+macro_rules! buffers {
+	($buffers:ident, $size:expr) => {
+		$crate::rpc::mock::buffers!($buffers, $size, $size, $size)
+	};
+	($buffers:ident, $in_size:expr, $out_size:expr, $req_size:expr) => {
+		let (mut in_buf, mut out_buf, mut req_buf) = ([0_u8; $in_size], [0_u8; $out_size], [0_u8; $req_size]);
+		let $buffers = $crate::rpc::RpcBuffers {
+			in_buf: &mut in_buf,
+			out_buf: &mut out_buf,
+			req_buf: &mut req_buf,
+		};
+	};
+}
+pub(crate) use buffers;
+
+/// Clock moving forward by `step_ms` each time it is read
+pub(crate) struct MockClock {
+	now: Cell<u64>,
+	step_ms: u64,
+}
+
+impl MockClock {
+	pub(crate) fn new(step_ms: u64) -> MockClock {
+		MockClock {
+			now: Cell::new(0),
+			step_ms,
+		}
+	}
+}
+
+impl Clock for MockClock {
+	fn now_ms(&self) -> u64 {
+		let now = self.now.get();
+		self.now.set(now + self.step_ms);
+		now
+	}
+
+	fn delay_ms(&self, ms: u64) {
+		self.now.set(self.now.get() + ms);
+	}
+}
+
+/// Websocket frame sent by the node (not masked)
+pub(crate) fn frame(fin: bool, opcode: u8, payload: &[u8]) -> Vec<u8> {
+	let mut frame = Vec::new();
+	frame.push(if fin { 0x80 | opcode } else { opcode });
+	if payload.len() < 126 {
+		frame.push(payload.len() as u8);
+	} else {
+		frame.push(126);
+		frame.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+	}
+	frame.extend_from_slice(payload);
+	frame
+}
+
+/// Text message sent by the node in one frame
+pub(crate) fn text(msg: &str) -> Step {
+	Step::Data(frame(true, 1, msg.as_bytes()))
+}
+
+/// HTTP response with `body`
+pub(crate) fn http_response(body: &str) -> Step {
+	Step::Data(format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body).into_bytes())
+}
+
+/// Response of the node accepting the websocket handshake request `req`
+pub(crate) fn handshake_response(req: &[u8]) -> Vec<u8> {
+	let req = String::from_utf8_lossy(req);
+	let key = req.split("\r\n")
+		.find(|line| line.to_ascii_lowercase().starts_with("sec-websocket-key:"))
+		.map(|line| line["sec-websocket-key:".len()..].trim())
+		.unwrap_or("");
+
+	format!("HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+		Sec-WebSocket-Accept: {}\r\n\r\n", accept_key(key)).into_bytes()
+}
+
+/// Parses the masked frames sent by the client, `data` holding whole frames
+fn unmask_frames(data: &[u8], frames: &mut Vec<(u8, Vec<u8>)>) {
+	let mut rest = data;
+	while rest.len() >= 2 {
+		let opcode = rest[0] & 0x0f;
+		let (len, start) = match rest[1] & 0x7f {
+			126 => (usize::from(u16::from_be_bytes([rest[2], rest[3]])), 4),
+			127 => {
+				let mut len = [0_u8; 8];
+				len.copy_from_slice(&rest[2..10]);
+				(u64::from_be_bytes(len) as usize, 10)
+			}
+			len => (usize::from(len), 2),
+		};
+		let mask = &rest[start..start+4];
+		let payload = rest[start+4..start+4+len].iter()
+			.enumerate()
+			.map(|(i, byte)| byte ^ mask[i % 4])
+			.collect();
+		frames.push((opcode, payload));
+		rest = &rest[start+4+len..];
+	}
+}
+
+/// `Sec-WebSocket-Accept` header expected for `key`
+fn accept_key(key: &str) -> String {
+	let mut data = Vec::new();
+	data.extend_from_slice(key.as_bytes());
+	data.extend_from_slice(b"258EAFA5-E914-47DA-95CA-C5AB0DC85B11");
+	base64(&sha1(&data))
+}
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+	let mut h: [u32; 5] = [0x6745_2301, 0xEFCD_AB89, 0x98BA_DCFE, 0x1032_5476, 0xC3D2_E1F0];
+
+	let mut msg = data.to_vec();
+	msg.push(0x80);
+	while msg.len() % 64 != 56 {
+		msg.push(0);
+	}
+	msg.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+
+	for block in msg.chunks(64) {
+		let mut w = [0_u32; 80];
+		for (i, word) in block.chunks(4).enumerate() {
+			w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+		}
+		for i in 16..80 {
+			w[i] = (w[i-3] ^ w[i-8] ^ w[i-14] ^ w[i-16]).rotate_left(1);
+		}
+
+		let [mut a, mut b, mut c, mut d, mut e] = h;
+		for (i, word) in w.iter().enumerate() {
+			let (f, k) = match i {
+				0..=19 => ((b & c) | (!b & d), 0x5A82_7999),
+				20..=39 => (b ^ c ^ d, 0x6ED9_EBA1),
+				40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+				_ => (b ^ c ^ d, 0xCA62_C1D6),
+			};
+			let temp = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(*word);
+			e = d;
+			d = c;
+			c = b.rotate_left(30);
+			b = a;
+			a = temp;
+		}
+		for (h, v) in h.iter_mut().zip([a, b, c, d, e].iter()) {
+			*h = h.wrapping_add(*v);
+		}
+	}
+
+	let mut hash = [0_u8; 20];
+	for (chunk, h) in hash.chunks_mut(4).zip(h.iter()) {
+		chunk.copy_from_slice(&h.to_be_bytes());
+	}
+	hash
+}
+
+fn base64(data: &[u8]) -> String {
+	const CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+	let mut encoded = String::new();
+	for chunk in data.chunks(3) {
+		let n = chunk.iter().enumerate().fold(0_u32, |n, (i, b)| n | u32::from(*b) << (16 - 8 * i));
+		for i in 0..4 {
+			if i <= chunk.len() {
+				encoded.push(char::from(CHARS[((n >> (18 - 6 * i)) & 0x3f) as usize]));
+			} else {
+				encoded.push('=');
+			}
+		}
+	}
+	encoded
+}
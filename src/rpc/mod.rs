@@ -1,17 +1,46 @@
 use embedded_websocket as ws;
 use embedded_websocket::{WebSocketOptions, WebSocketSendMessageType, WebSocketReceiveMessageType, WebSocketCloseStatusCode};
-use embedded_nal::{TcpClient};
+use embedded_nal::{Dns, AddrType, IpAddr, SocketAddr};
 use rand::rngs::SmallRng;
 use core::str::FromStr;
 use serde::{Serialize, Deserialize};
-use heapless::{String, consts::*};
 use crate::TcpError;
+use crate::transport::Transport;
 use rand::{SeedableRng};
+use link::Link;
+
+pub use endpoint::{Endpoint, Scheme};
+pub use batch::{BatchRequest, BatchResponse};
+pub use error::{NodeError, AuthorError, InvalidTransaction};
+pub use subscription::SubscriptionId;
+
+mod batch;
+mod endpoint;
+mod error;
+mod http;
+mod link;
+mod subscription;
+
+#[cfg(test)]
+pub(crate) mod mock;
+#[cfg(test)]
+mod tests;
 
 #[derive(Debug)]
 pub enum JsonError {
 	ErrorParsing,
-	ErrorCode(i64)
+	ErrorSerializing,
+	/// Error returned by the node, see [`NodeError::author_error`](struct.NodeError.html#method.author_error)
+	/// for errors of extrinsic submissions
+	ErrorCode(NodeError)
+}
+
+#[derive(Debug)]
+pub enum HttpError {
+	/// Response head or chunked body cannot be parsed
+	InvalidResponse,
+	/// Status code of the response is not successful (2xx)
+	Status(u16),
 }
 
 #[derive(Debug)]
@@ -19,13 +48,36 @@ pub enum RpcError {
 	WebSocket(ws::Error),
 	TcpSocket(TcpError),
 	Embedded(embedded_nal::nb::Error<TcpError>),
+	/// The transport returned an error, see [`transport_error`](struct.Rpc.html#method.transport_error)
+	Transport,
 	Json(JsonError),
+	Http(HttpError),
 	ResponseDoesNotMatch,
+	/// Message received is larger than the buffers provided
+	MessageTooLarge,
+	ConnectionClosed,
+	/// TCP stack kept returning `WouldBlock` until the timeout expired
+	Timeout,
+	/// Subscriptions need a WebSocket connection, they cannot be made over HTTP
+	SubscriptionNotSupported,
 	ErrorClosing,
 	Utf8Error,
 	Unknown
 }
 
+impl RpcError {
+	/// Returns true if the connection has been closed, reset or has timed out, which can be recovered
+	/// by reconnecting. Errors which would happen again on a new connection (invalid address, DNS,
+	/// websocket protocol...) are not, neither is `WouldBlock` returned without clock.
+	///
+	/// `Transport` errors are not either: only the transport knows whether they can be recovered,
+	/// see [`Rpc::is_connection_lost`](struct.Rpc.html#method.is_connection_lost).
+	pub fn is_connection_lost(&self) -> bool {
+		matches!(self, RpcError::ConnectionClosed | RpcError::Timeout
+			| RpcError::TcpSocket(TcpError::CountNotMatching) | RpcError::TcpSocket(TcpError::CannotConnect))
+	}
+}
+
 impl From<ws::Error> for RpcError {
 	fn from(err: ws::Error) -> RpcError {
 		RpcError::WebSocket(err)
@@ -50,6 +102,12 @@ impl From<JsonError> for RpcError {
 	}
 }
 
+impl From<HttpError> for RpcError {
+	fn from(err: HttpError) -> RpcError {
+		RpcError::Http(err)
+	}
+}
+
 impl From<core::str::Utf8Error> for RpcError {
 	fn from(_: core::str::Utf8Error) -> RpcError {
 		RpcError::Utf8Error
@@ -66,13 +124,135 @@ impl From<embedded_nal::nb::Error<RpcError>> for RpcError {
 	}
 }
 
-pub struct Rpc<'a, S> {
-	socket: Option<S>,
+/// Buffers used by [`Rpc`](struct.Rpc.html), to be sized depending on the available memory
+/// and the expected size of the requests and responses.
+pub struct RpcBuffers<'a> {
+	/// Data received from the TCP socket
+	pub in_buf: &'a mut [u8],
+	/// Websocket frames to be sent and payload of the received messages (responses)
+	pub out_buf: &'a mut [u8],
+	/// Serialized JSON-RPC requests
+	pub req_buf: &'a mut [u8],
+}
+
+/// Writes formatted text into a byte slice
+pub(crate) struct SliceWriter<'b> {
+	buf: &'b mut [u8],
+	len: usize,
+}
+
+impl<'b> SliceWriter<'b> {
+	pub(crate) fn new(buf: &'b mut [u8]) -> SliceWriter<'b> {
+		SliceWriter {
+			buf,
+			len: 0,
+		}
+	}
+
+	/// Size of the text written
+	pub(crate) fn len(&self) -> usize {
+		self.len
+	}
+}
+
+impl core::fmt::Write for SliceWriter<'_> {
+	fn write_str(&mut self, s: &str) -> core::fmt::Result {
+		let end = self.len + s.len();
+		if end > self.buf.len() {
+			return Err(core::fmt::Error)
+		}
+		self.buf[self.len..end].copy_from_slice(s.as_bytes());
+		self.len = end;
+		Ok(())
+	}
+}
+
+/// Clock provided by the user to measure timeouts, needed to poll non-blocking TCP stacks.
+pub trait Clock {
+	/// Returns a monotonic time in milliseconds
+	fn now_ms(&self) -> u64;
+
+	/// Waits for `ms` milliseconds, used between reconnection attempts (see
+	/// [`ReconnectPolicy`](struct.ReconnectPolicy.html)).
+	///
+	/// The default implementation busy waits, polling `now_ms` and keeping the CPU running for up to
+	/// `max_backoff_ms`: implement it with the delay or sleep of the platform on devices which should
+	/// save power.
+	fn delay_ms(&self, ms: u64) {
+		let start = self.now_ms();
+		while self.now_ms().saturating_sub(start) < ms {}
+	}
+}
+
+/// Resolves node hostnames into IP addresses.
+/// Implemented for any TCP stack implementing [`embedded_nal::Dns`](../../embedded_nal/trait.Dns.html),
+/// or can be implemented by the user.
+pub trait Resolver {
+	fn resolve(&self, hostname: &str) -> Result<IpAddr, TcpError>;
+}
+
+impl<T: Dns> Resolver for T {
+	fn resolve(&self, hostname: &str) -> Result<IpAddr, TcpError> {
+		self.gethostbyname(hostname, AddrType::Either)
+			.map_err(|_| TcpError::CannotResolve)
+	}
+}
+
+/// Policy used to recover from a lost connection: the connection is established again
+/// and idempotent requests are sent again, with an exponential backoff between attempts.
+/// The backoff needs a [`Clock`](trait.Clock.html), attempts are made without delay otherwise.
+#[derive(Debug, Clone, Copy)]
+pub struct ReconnectPolicy {
+	/// Maximum number of reconnections for one request, `0` disables reconnection
+	pub max_retries: u8,
+	/// Delay before the first reconnection, doubled for each new attempt
+	pub initial_backoff_ms: u64,
+	/// Maximum delay between two attempts
+	pub max_backoff_ms: u64,
+}
+
+impl Default for ReconnectPolicy {
+	fn default() -> ReconnectPolicy {
+		ReconnectPolicy {
+			max_retries: 3,
+			initial_backoff_ms: 500,
+			max_backoff_ms: 8000,
+		}
+	}
+}
+
+impl ReconnectPolicy {
+	/// Delay to wait before attempt number `attempt` (starting at 0)
+	pub fn backoff_ms(&self, attempt: u8) -> u64 {
+		self.initial_backoff_ms
+			.saturating_mul(1_u64.checked_shl(attempt as u32).unwrap_or(u64::MAX))
+			.min(self.max_backoff_ms)
+	}
+}
+
+/// Protocol used over the current connection
+enum Session<'a> {
+	Closed,
+	WebSocket,
+	/// One request per connection, the endpoint is kept to build the HTTP requests
+	Http(Endpoint<'a>),
+}
+
+pub struct Rpc<'a, T: Transport> {
+	link: Link<'a, T>,
+	resolver: Option<&'a dyn Resolver>,
 	ws: ws::WebSocketClient<SmallRng>,
-	in_buf: [u8; 4096],
-	out_buf: [u8; 4096],
-	tcp: &'a dyn TcpClient<TcpSocket=S, Error=TcpError>,
+	in_buf: &'a mut [u8],
+	out_buf: &'a mut [u8],
+	req_buf: &'a mut [u8],
+	in_len: usize,
+	session: Session<'a>,
+	policy: ReconnectPolicy,
 	cmd_id: usize,
+	/// Notifications have been received while waiting for a response, and dropped
+	dropped_notifications: bool,
+	/// Number of websocket sessions opened
+	connections: usize,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -87,12 +267,33 @@ struct JsonRpc<'a, T> {
 	params: Option<T>,
 }
 
+#[derive(Deserialize)]
+struct JsonId {
+	/// `null` when the request cannot be parsed by the node
+	id: Option<usize>,
+}
+
+/// Response with a result of type `R`
+#[derive(Deserialize)]
+struct JsonResult<R> {
+	result: Option<R>,
+}
+
 #[derive(Serialize, Deserialize)]
 struct ErrorCode <'a> {
 	#[serde(skip_serializing_if = "Option::is_none")]
 	code: Option<i64>,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	message: Option<&'a str>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	data: Option<&'a str>,
+}
+
+/// Same as `ErrorCode`, when `data` is not a string
+#[derive(Deserialize)]
+struct ErrorCodeOnly<'a> {
+	code: Option<i64>,
+	message: Option<&'a str>,
 }
 
 //"{\"jsonrpc\":\"2.0\",\"error\":{\"code\":-32600,\"message\":\"Invalid request\"},\"id\":7}"
@@ -104,73 +305,166 @@ struct JsonErrorResponse<'a> {
 	error: Option<ErrorCode<'a>>
 }
 
-impl<'a, S> Rpc<'a, S>
-{
-	/// Instantiates the provider and init TCP socket, websocket lib and static buffers.
-	///
-	/// # Errors
-	/// * `TcpError::CannotCreate` if the TCP socket cannot be created
-	pub fn new(tcp: &dyn TcpClient<TcpSocket=S, Error=TcpError>) -> Result<Rpc<S>, RpcError> {
-		let sock = tcp.socket().ok();
+#[derive(Deserialize)]
+struct JsonErrorCodeResponse<'a> {
+	#[serde(borrow)]
+	error: Option<ErrorCodeOnly<'a>>
+}
 
+impl<'a, T: Transport> Rpc<'a, T>
+{
+	/// Instantiates the provider and init websocket lib, using the given transport and buffers.
+	pub fn new(transport: T, buffers: RpcBuffers<'a>) -> Result<Rpc<'a, T>, RpcError> {
 		Ok(Rpc {
-			tcp,
-			socket: sock,
+			link: Link::new(transport),
+			resolver: None,
 			ws: ws::WebSocketClient::new_client(SmallRng::seed_from_u64(12)),
-			in_buf: [0_u8; 4096],
-			out_buf: [0_u8; 4096],
+			in_buf: buffers.in_buf,
+			out_buf: buffers.out_buf,
+			req_buf: buffers.req_buf,
+			in_len: 0,
+			session: Session::Closed,
+			policy: ReconnectPolicy::default(),
 			cmd_id: 1_usize,
+			dropped_notifications: false,
+			connections: 0,
 		})
 	}
 
-	/// Connects to the node at the given address. Initiates the websocket handshake.
+	/// Sets the clock used to retry TCP operations returning `WouldBlock`, during `timeout_ms` at most.
+	/// Without clock, `WouldBlock` is returned as an error. Either way, a websocket request failing
+	/// once sent closes the connection, as its late response would be read by the next request.
+	pub fn set_clock(&mut self, clock: &'a dyn Clock, timeout_ms: u64) {
+		self.link.set_clock(clock, timeout_ms);
+	}
+
+	/// Returns the last error returned by the transport, which caused [`RpcError::Transport`](enum.RpcError.html#variant.Transport)
+	pub fn transport_error(&self) -> Option<&T::Error> {
+		self.link.error()
+	}
+
+	/// Sets the resolver used to connect to endpoints given by hostname instead of IP address
+	pub fn set_resolver(&mut self, resolver: &'a dyn Resolver) {
+		self.resolver.replace(resolver);
+	}
+
+	/// Connects to the node at the given endpoint. Initiates the websocket handshake, except for
+	/// `http`/`https` endpoints for which the connection is used by the next request only.
+	/// The endpoint host is resolved if it's not an IP address.
 	///
 	/// # Errors
 	/// * `embedded_websocket::Error`: if any error with websocket
-	/// * `TcpError::InvalidAddress`: address cannot be parsed
-	/// * `TcpError::CountNotMatching`: sent bytes count doesn't equal the initial packet count
-	pub fn connect(&mut self, address: &str) -> Result<(), RpcError> {
-		// TCP connection first
-		if let Ok(addr) = embedded_nal::SocketAddr::from_str(address) {
-			if let Some(mut s) = self.socket.as_mut() {
-				self.tcp.connect(&mut s, addr)?;
-			} else {
-				return Err(RpcError::TcpSocket(TcpError::CannotConnect))
-			}
+	/// * `TcpError::InvalidAddress`: endpoint host is not an IP address and no resolver has been set,
+	/// or headers are too long
+	/// * `TcpError::CannotResolve`: endpoint host cannot be resolved
+	/// * `TcpError::CountNotMatching`: the TCP stack doesn't accept the handshake request
+	/// * `MessageTooLarge`: the handshake response doesn't fit into `in_buf`
+	/// * `ConnectionClosed`: the connection has been closed before the whole response has been received
+	///
+	/// The transport is closed on any error, a new connection being opened on the next attempt.
+	pub fn connect(&mut self, endpoint: &Endpoint<'a>) -> Result<(), RpcError> {
+		let res = self.open(endpoint);
+		if res.is_err() {
+			self.close();
+		}
+		res
+	}
+
+	/// Opens the connection and performs the websocket handshake, see [`connect`](#method.connect)
+	fn open(&mut self, endpoint: &Endpoint<'a>) -> Result<(), RpcError> {
+		let ip = if let Ok(ip) = IpAddr::from_str(endpoint.host) {
+			ip
+		} else if let Some(resolver) = self.resolver {
+			resolver.resolve(endpoint.host)?
 		} else {
 			return Err(RpcError::TcpSocket(TcpError::InvalidAddress))
+		};
+
+		// transport connection first
+		self.link.connect(SocketAddr::new(ip, endpoint.port), endpoint.host)?;
+		self.in_len = 0;
+
+		if endpoint.is_http() {
+			self.session = Session::Http(*endpoint);
+			return Ok(())
 		}
 
-		// initiate a websocket opening handshake
+		// initiate a websocket opening handshake, from a new websocket client in case of reconnection
+		self.ws = ws::WebSocketClient::new_client(SmallRng::seed_from_u64(self.cmd_id as u64));
+		let host = endpoint.host_header()?;
+		let origin = endpoint.origin_header()?;
 		let websocket_options = WebSocketOptions {
-			path: "",
-			host: "localhost:9944",
-			origin: "http://localhost:9944",
+			path: endpoint.path,
+			host: host.as_str(),
+			origin: origin.as_str(),
 			sub_protocols: None,
-			additional_headers: None,
+			additional_headers: endpoint.headers,
 		};
-		let (len, web_socket_key) = self.ws.client_connect(&websocket_options, &mut self.out_buf)?;
+		let (len, web_socket_key) = self.ws.client_connect(&websocket_options, self.out_buf)?;
 
-		// send websocket frame using tcp socket
-		let written = self.tcp.send(&mut self.socket.as_mut().unwrap(), &self.out_buf[..len])?;
-		if written != len {
-			return Err(RpcError::TcpSocket(TcpError::CountNotMatching))
-		}
+		// send websocket frame using the transport
+		self.link.send_all(&self.out_buf[..len])?;
 
-		// read the response from the server and check it to complete the opening handshake
-		let received_size = self.tcp.receive(&mut self.socket.as_mut().unwrap(), &mut self.in_buf)?;
-		self.ws.client_accept(&web_socket_key, &mut self.in_buf[..received_size])?;
+		// receive the whole response head from the server, and check it to complete the opening handshake
+		let mut len = 0_usize;
+		let head_len = loop {
+			if len == self.in_buf.len() {
+				return Err(RpcError::MessageTooLarge)
+			}
+			let received_size = self.link.receive(&mut self.in_buf[len..])?;
+			if received_size == 0 {
+				return Err(RpcError::ConnectionClosed)
+			}
+			len += received_size;
+
+			if let Some(end) = self.in_buf[..len].windows(4).position(|w| w == b"\r\n\r\n") {
+				break end + 4
+			}
+		};
+		self.ws.client_accept(&web_socket_key, &mut self.in_buf[..head_len])?;
+
+		// frames received along with the head are kept for the next message
+		self.in_buf.copy_within(head_len..len, 0);
+		self.in_len = len - head_len;
+		self.session = Session::WebSocket;
+		self.connections += 1;
 
 		Ok(())
 	}
 
-	/// Returns TCP socket state
+	/// Returns connection state: transport is connected and websocket is open
+	/// (or HTTP request not sent yet)
 	pub fn is_connected(&self) -> bool {
-		let c = self.socket.as_ref()
-			.map(|socket| self.tcp.is_connected(socket))
-			.unwrap_or(Ok(false))
-			.unwrap_or(false);
-		c
+		!matches!(self.session, Session::Closed) && self.link.is_connected()
+	}
+
+	/// Number of websocket sessions opened so far, including the ones opened again by
+	/// [`call`](#method.call) when reconnecting. Subscriptions are lost when it changes.
+	pub fn connections(&self) -> usize {
+		self.connections
+	}
+
+	/// Returns true if `error` can be recovered by reconnecting, see
+	/// [`RpcError::is_connection_lost`](enum.RpcError.html#method.is_connection_lost). `Transport`
+	/// errors are recoverable if the transport says so (see [`Transport::is_recoverable`](../transport/trait.Transport.html#method.is_recoverable)).
+	pub fn is_connection_lost(&self, error: &RpcError) -> bool {
+		match error {
+			RpcError::Transport => self.link.is_recoverable(),
+			e => e.is_connection_lost(),
+		}
+	}
+
+	/// Sets the policy used by [`call`](#method.call) to reconnect and retry requests
+	pub fn set_reconnect_policy(&mut self, policy: ReconnectPolicy) {
+		self.policy = policy;
+	}
+
+	/// Closes the transport without any websocket close handshake,
+	/// the connection is opened again on the next call.
+	fn close(&mut self) {
+		self.session = Session::Closed;
+		self.in_len = 0;
+		self.link.close();
 	}
 
 	/// Disconnects from the node by initiating a close handshake.
@@ -179,80 +473,388 @@ impl<'a, S> Rpc<'a, S>
 	/// # Errors
 	/// * `ErrorClosing` if the WebSocket has not been closed properly.
 	pub fn disconnect(&mut self) -> Result<(), RpcError> {
+		if !matches!(self.session, Session::WebSocket) {
+			self.close();
+			return Ok(())
+		}
+
 		// initiate a close handshake
-		let send_size = self.ws.close(WebSocketCloseStatusCode::NormalClosure, None, &mut self.out_buf)?;
-		self.tcp.send(&mut self.socket.as_mut().unwrap(), &self.out_buf[..send_size])?;
-
-		// read the reply from the server to complete the close handshake
-		let received_size = self.tcp.receive(&mut self.socket.as_mut().unwrap(), &mut self.in_buf)?;
-
-		let ws_result = self.ws.read(&self.in_buf[..received_size], &mut self.out_buf)?;
-		match ws_result.message_type {
-			WebSocketReceiveMessageType::CloseCompleted => {
-				// we can close the TCP socket as well
-				self.socket.take().and_then(|socket| self.tcp.close(socket).ok());
-				Ok(())
-			}
-			_ => {
-				Err(RpcError::ErrorClosing)
+		let send_size = self.ws.close(WebSocketCloseStatusCode::NormalClosure, None, self.out_buf)?;
+		self.link.send_all(&self.out_buf[..send_size])?;
+
+		// read the reply from the server to complete the close handshake,
+		// messages received in the meantime are dropped
+		loop {
+			match self.read_message() {
+				Ok(_) => {}
+				Err(RpcError::ConnectionClosed) => {
+					// TCP socket has been closed as well
+					return Ok(())
+				}
+				Err(_) => {
+					return Err(RpcError::ErrorClosing)
+				}
 			}
 		}
 	}
 
-	/// Send request with response (blocking wait)
-	fn request(&mut self, message: &str) -> Result<&str, RpcError> {
-		// create WS frame with message argument as payload
+	/// Send request located in `req_buf[..req_len]` with response (blocking wait).
+	/// Returns the size of the response, located in `out_buf`.
+	fn request(&mut self, req_len: usize) -> Result<usize, RpcError> {
+		match self.session {
+			Session::WebSocket => {}
+			Session::Http(endpoint) => {
+				// the connection is not reused, whatever the result
+				let res = self.http_request(&endpoint, req_len);
+				self.close();
+				return res
+			}
+			Session::Closed => {
+				return Err(RpcError::ConnectionClosed)
+			}
+		}
+
+		// create WS frame with request as payload
 		let len = self.ws.write(
 			WebSocketSendMessageType::Text,
 			true,
-			message.as_ref(),
-			&mut self.out_buf,
+			&self.req_buf[..req_len],
+			self.out_buf,
 		)?;
 
-		// send websocket frame
-		let written = self.tcp.send(&mut self.socket.as_mut().unwrap(), &mut self.out_buf[..len])?;
-		if len != written {
-			return Err(RpcError::TcpSocket(TcpError::CountNotMatching))
+		// once the request has been sent, the session is closed on any error (e.g. `Timeout` before
+		// the response), otherwise the late response would be read as the response to the next request
+		let res = self.exchange(len);
+		if res.is_err() {
+			self.close();
+		}
+		res
+	}
+
+	/// Sends the websocket frame located in `out_buf[..len]` and reads the response, located in
+	/// `out_buf` as well. Notifications received in the meantime are dropped.
+	fn exchange(&mut self, len: usize) -> Result<usize, RpcError> {
+		self.link.send_all(&self.out_buf[..len])?;
+
+		loop {
+			let msg_len = self.read_message()?;
+			if !subscription::is_notification(&self.out_buf[..msg_len]) {
+				return Ok(msg_len)
+			}
+			self.dropped_notifications = true;
+		}
+	}
+
+	/// Sends the request located in `req_buf[..req_len]` as the body of an HTTP POST request,
+	/// returns the size of the response body, located in `out_buf`.
+	///
+	/// # Errors
+	/// * `MessageTooLarge`: the response head doesn't fit into `in_buf`, or the body into `out_buf`
+	/// * `HttpError::InvalidResponse`: the response cannot be parsed
+	/// * `HttpError::Status`: the response status is not successful
+	/// * `ConnectionClosed`: the connection has been closed before the whole head has been received
+	fn http_request(&mut self, endpoint: &Endpoint, req_len: usize) -> Result<usize, RpcError> {
+		let head_len = http::write_request_head(endpoint, req_len, self.out_buf)?;
+		self.link.send_all(&self.out_buf[..head_len])?;
+		self.link.send_all(&self.req_buf[..req_len])?;
+
+		// receive the response head into `in_buf`
+		let mut len = 0_usize;
+		let head = loop {
+			if len == self.in_buf.len() {
+				return Err(RpcError::MessageTooLarge)
+			}
+			let received_size = self.link.receive(&mut self.in_buf[len..])?;
+			if received_size == 0 {
+				return Err(RpcError::ConnectionClosed)
+			}
+			len += received_size;
+
+			if let Some(head) = http::parse_response_head(&self.in_buf[..len])? {
+				break head
+			}
+		};
+		// the body of error responses is not read, it may not even fit into `out_buf`
+		if !(200..300).contains(&head.status) {
+			return Err(RpcError::Http(HttpError::Status(head.status)))
 		}
 
-		// read the response from the server and parse websocket message
-		let received_size = self.tcp.receive(&mut self.socket.as_mut().unwrap(), &mut self.in_buf)?;
-		let ws_result = self.ws.read(&self.in_buf[..received_size], &mut self.out_buf)?;
-		match ws_result.message_type {
-			WebSocketReceiveMessageType::Text => {
-				let res = core::str::from_utf8(&self.out_buf[..ws_result.len_to])?;
-				Ok(res)
+		// part of the body received along with the head
+		let mut body_len = len - head.len;
+		if body_len > self.out_buf.len() {
+			return Err(RpcError::MessageTooLarge)
+		}
+		self.out_buf[..body_len].copy_from_slice(&self.in_buf[head.len..len]);
+
+		// the rest of the body, until `Content-Length` or the end of the connection
+		loop {
+			if let Some(content_length) = head.content_length {
+				if body_len >= content_length {
+					body_len = content_length;
+					break
+				}
 			}
-			WebSocketReceiveMessageType::CloseMustReply => {
-				// Signals that the other party has initiated the close handshake. If you receive this
-				// message you should respond with a `WebSocketSendMessageType::CloseReply` with the
-				// same payload as close message
-				// TODO not tested
-				let len = self.ws.write(
-					WebSocketSendMessageType::CloseReply,
-					true,
-					&self.out_buf[..ws_result.len_to], // take payload from received message
-					&mut self.in_buf,
-				)?;
-				self.tcp.send(&mut self.socket.as_mut().unwrap(), &mut self.in_buf[..len])?;
-
-				Err(RpcError::WebSocket(ws::Error::Unknown))
+			if body_len == self.out_buf.len() {
+				return Err(RpcError::MessageTooLarge)
 			}
-			_ => {
-				Err(RpcError::WebSocket(ws::Error::Unknown))
+			let received_size = self.link.receive(&mut self.out_buf[body_len..])?;
+			if received_size == 0 {
+				break
 			}
+			body_len += received_size;
+		}
+
+		if head.chunked {
+			body_len = http::decode_chunked(&mut self.out_buf[..body_len])?;
 		}
+
+		Ok(body_len)
 	}
 
-	/// Call rpc method with optional params
-	/// Field `result` is returned from the response if it can be parsed as a string
-	/// Otherwise, the whole JSON response is returned.
+	/// Reads the next text or binary websocket message into `out_buf` and returns its size.
+	/// TCP data is received until the message is complete, as frames can be split over several
+	/// TCP reads and messages over several frames (continuation frames).
+	/// Pings received in between are answered, pongs are ignored.
+	/// Bytes received after the end of the message are kept in `in_buf` for the next message.
+	///
+	/// # Errors
+	/// * `MessageTooLarge`: the message doesn't fit into `out_buf`, or a frame doesn't fit into `in_buf`
+	/// * `ConnectionClosed`: the other party closed the websocket or the TCP connection
+	/// * any other error from the websocket lib or the TCP stack
+	///
+	/// The connection is closed when the message cannot be read to its end (e.g. `MessageTooLarge`, or
+	/// `Timeout` after part of the message has been received), as the rest of it would then be read
+	/// as a new message.
+	fn read_message(&mut self) -> Result<usize, RpcError> {
+		let mut msg_len = 0_usize;
+
+		loop {
+			// parse frames already received
+			if self.in_len > 0 {
+				match self.ws.read(&self.in_buf[..self.in_len], &mut self.out_buf[msg_len..]) {
+					Ok(ws_result) => {
+						// drop the bytes read from `in_buf`
+						self.in_buf.copy_within(ws_result.len_from..self.in_len, 0);
+						self.in_len -= ws_result.len_from;
+
+						match ws_result.message_type {
+							WebSocketReceiveMessageType::Text | WebSocketReceiveMessageType::Binary => {
+								msg_len += ws_result.len_to;
+								if ws_result.end_of_message {
+									return Ok(msg_len)
+								} else if msg_len == self.out_buf.len() {
+									self.close();
+									return Err(RpcError::MessageTooLarge)
+								}
+							}
+							WebSocketReceiveMessageType::Ping => {
+								// reply with the same payload, the frame is written after the payload
+								// so that `req_buf` is kept untouched in case the request is sent again
+								let (payload, frame) = self.out_buf[msg_len..].split_at_mut(ws_result.len_to);
+								let len = self.ws.write(WebSocketSendMessageType::Pong, true, payload, frame)?;
+								self.link.send_all(&frame[..len])?;
+							}
+							WebSocketReceiveMessageType::Pong => {}
+							WebSocketReceiveMessageType::CloseMustReply => {
+								// Signals that the other party has initiated the close handshake. If you receive this
+								// message you should respond with a `WebSocketSendMessageType::CloseReply` with the
+								// same payload as close message
+								let (payload, frame) = self.out_buf[msg_len..].split_at_mut(ws_result.len_to);
+								let len = self.ws.write(WebSocketSendMessageType::CloseReply, true, payload, frame)?;
+								self.link.send_all(&frame[..len])?;
+
+								self.close();
+								return Err(RpcError::ConnectionClosed)
+							}
+							WebSocketReceiveMessageType::CloseCompleted => {
+								self.close();
+								return Err(RpcError::ConnectionClosed)
+							}
+						}
+
+						continue;
+					}
+					Err(ws::Error::ReadFrameIncomplete) => {
+						// more data needed to parse the frame
+					}
+					Err(e) => {
+						return Err(RpcError::WebSocket(e))
+					}
+				}
+			}
+
+			if self.in_len == self.in_buf.len() {
+				// a whole frame cannot fit into `in_buf`
+				self.close();
+				return Err(RpcError::MessageTooLarge)
+			}
+
+			let received_size = match self.link.receive(&mut self.in_buf[self.in_len..]) {
+				Ok(size) => size,
+				Err(e) => {
+					// frames of the message already received would be lost, a partial frame
+					// in `in_buf` is kept for the next call
+					if msg_len > 0 {
+						self.close();
+					}
+					return Err(e)
+				}
+			};
+			if received_size == 0 {
+				self.close();
+				return Err(RpcError::ConnectionClosed)
+			}
+			self.in_len += received_size;
+		}
+	}
+
+	/// Call rpc method with optional params.
+	/// Field `result` is deserialized as `R`: string (borrowed from the buffers), number, struct,
+	/// array... A `null` result is returned if `R` accepts it (e.g. `Option`).
 	///
 	/// # Errors
 	/// * `ResponseDoesNotMatch`: JSON returned has been parsed but returned `id` is not the same as
 	/// the sent `id`
+	/// * `Json(ErrorCode)`: the node returned an error
+	/// * `Json(ErrorParsing)`: `result` cannot be deserialized as `R`
 	/// * any other error than can happen with `request()`
-	pub fn rpc_method<T: Serialize>(&mut self, method: Option<&str>, params: Option<T>) -> Result<&str, RpcError> {
+	pub fn rpc_method<'s, P: Serialize, R: Deserialize<'s>>(&'s mut self, method: Option<&str>, params: Option<P>) -> Result<R, RpcError> {
+		let (id, req_len) = self.serialize(method, params)?;
+		let msg_len = self.request(req_len)?;
+		self.parse_response(id, msg_len)
+	}
+
+	/// Same as [`rpc_method`](#method.rpc_method), connecting to `endpoint` first if needed.
+	/// If `idempotent` is set, the request is sent again after reconnecting when the connection
+	/// is lost, according to the [`ReconnectPolicy`](struct.ReconnectPolicy.html).
+	///
+	/// # Errors
+	/// * any error than can happen with `connect()` or `rpc_method()`
+	pub fn call<'s, P: Serialize, R: Deserialize<'s>>(&'s mut self, endpoint: &Endpoint<'a>, idempotent: bool, method: Option<&str>, params: Option<P>) -> Result<R, RpcError> {
+		let (id, req_len) = self.serialize(method, params)?;
+		let msg_len = self.send(endpoint, idempotent, req_len)?;
+
+		self.parse_response(id, msg_len)
+	}
+
+	/// Sends several requests in one message (JSON-RPC batch), connecting to `endpoint` first if needed.
+	/// Requests are sent again after reconnecting if `idempotent` is set, as with [`call`](#method.call).
+	/// The results are then read from the returned [`BatchResponse`](struct.BatchResponse.html),
+	/// by index of the request in `requests`.
+	///
+	/// # Errors
+	/// * `Json(ErrorSerializing)`: the requests don't fit into `req_buf`
+	/// * any error than can happen with `connect()` or `rpc_method()`
+	pub fn call_batch(&mut self, endpoint: &Endpoint<'a>, idempotent: bool, requests: &[BatchRequest]) -> Result<BatchResponse, RpcError> {
+		let first_id = self.cmd_id;
+		let req_len = batch::serialize(requests, first_id, self.req_buf)?;
+		self.cmd_id += requests.len();
+
+		let msg_len = self.send(endpoint, idempotent, req_len)?;
+		let response = core::str::from_utf8(&self.out_buf[..msg_len])?;
+
+		Ok(BatchResponse::new(response, first_id, requests.len()))
+	}
+
+	/// Subscribes with `method` (e.g. `state_subscribeStorage`), connecting to `endpoint` first if needed.
+	/// The notifications are then read with [`next_notification`](#method.next_notification).
+	/// Subscriptions are lost with the connection: subscribe again when an error shows that the
+	/// connection has been lost (see [`is_connection_lost`](#method.is_connection_lost)),
+	/// or when [`connections`](#method.connections) has changed since subscribing.
+	///
+	/// # Errors
+	/// * `SubscriptionNotSupported`: `endpoint` is an `http`/`https` endpoint
+	/// * any error than can happen with `call()`
+	pub fn subscribe<P: Serialize>(&mut self, endpoint: &Endpoint<'a>, method: &str, params: Option<P>) -> Result<SubscriptionId, RpcError> {
+		if endpoint.is_http() {
+			return Err(RpcError::SubscriptionNotSupported)
+		}
+
+		let (id, req_len) = self.serialize(Some(method), params)?;
+		let msg_len = self.send(endpoint, true, req_len)?;
+		let res = core::str::from_utf8(&self.out_buf[..msg_len])?;
+
+		subscription::parse_id(res, id)
+	}
+
+	/// Cancels the subscription `id` with `method` (e.g. `state_unsubscribeStorage`),
+	/// returns false if the node doesn't know the subscription.
+	///
+	/// # Errors
+	/// * any error than can happen with `call()`
+	pub fn unsubscribe(&mut self, endpoint: &Endpoint<'a>, method: &str, id: &SubscriptionId) -> Result<bool, RpcError> {
+		match id {
+			SubscriptionId::Str(id) => self.call(endpoint, true, Some(method), Some([id.as_str()])),
+			SubscriptionId::Num(id) => self.call(endpoint, true, Some(method), Some([*id])),
+		}
+	}
+
+	/// Waits for the next notification `method` (e.g. `state_storage`) of the subscription `id`,
+	/// its `result` being deserialized as `R`. Other messages received in the meantime are dropped.
+	/// Returns `None` if no notification has been received before the timeout set with
+	/// [`set_clock`](#method.set_clock), or right away without clock if the transport would block.
+	///
+	/// # Errors
+	/// * `ConnectionClosed`: not connected to the node, the subscription has been lost
+	/// * `Json(ErrorParsing)`: `result` cannot be deserialized as `R`
+	/// * any other error than can happen while receiving a message
+	pub fn next_notification<'s, R: Deserialize<'s>>(&'s mut self, method: &str, id: &SubscriptionId) -> Result<Option<R>, RpcError> {
+		if !matches!(self.session, Session::WebSocket) {
+			return Err(RpcError::ConnectionClosed)
+		}
+
+		let msg_len = loop {
+			let msg_len = match self.read_message() {
+				Ok(len) => len,
+				Err(RpcError::Timeout) | Err(RpcError::Embedded(embedded_nal::nb::Error::WouldBlock)) => return Ok(None),
+				Err(e) => return Err(e),
+			};
+			if subscription::is_notification_for(&self.out_buf[..msg_len], method, id) {
+				break msg_len
+			}
+		};
+
+		let res = core::str::from_utf8(&self.out_buf[..msg_len])?;
+		subscription::parse_notification(res).map(Some)
+	}
+
+	/// Returns true if notifications have been dropped while waiting for a response since the
+	/// last call, in which case the state they notified should be read again.
+	pub fn take_dropped_notifications(&mut self) -> bool {
+		core::mem::replace(&mut self.dropped_notifications, false)
+	}
+
+	/// Sends the request located in `req_buf[..req_len]`, reconnecting and sending it again
+	/// if `idempotent` is set, returns the size of the response located in `out_buf`.
+	fn send(&mut self, endpoint: &Endpoint<'a>, idempotent: bool, req_len: usize) -> Result<usize, RpcError> {
+		let mut attempt = 0_u8;
+		loop {
+			let res = if self.is_connected() {
+				self.request(req_len)
+			} else {
+				self.connect(endpoint).and_then(|_| self.request(req_len))
+			};
+
+			match res {
+				Ok(len) => return Ok(len),
+				Err(e) => {
+					if !idempotent || !self.is_connection_lost(&e) || attempt >= self.policy.max_retries {
+						return Err(e)
+					}
+
+					// start again from a new socket
+					self.close();
+					if let Some(clock) = self.link.clock() {
+						clock.delay_ms(self.policy.backoff_ms(attempt));
+					}
+					attempt += 1;
+				}
+			}
+		}
+	}
+
+	/// Serializes the request into `req_buf`, returns the request `id` and size
+	fn serialize<P: Serialize>(&mut self, method: Option<&str>, params: Option<P>) -> Result<(usize, usize), RpcError> {
 		// construct request from method and params
 		let json_req = JsonRpc {
 			id: self.cmd_id,
@@ -261,42 +863,51 @@ impl<'a, S> Rpc<'a, S>
 			params,
 			result: None
 		};
-		let req_str: String<U512> = serde_json_core::to_string(&json_req).unwrap();
-		let req_str = req_str.as_str();
+		let req_len = serde_json_core::to_slice(&json_req, self.req_buf)
+			.map_err(|_| RpcError::Json(JsonError::ErrorSerializing))?;
 		self.cmd_id = self.cmd_id + 1_usize;
-		let response = self.request(req_str);
-
-		// Parse response if it contains a result string
-		// returns the whole response if JSON cannot be parsed
-		match response {
-			Ok(res) => {
-				if let Ok(json_res) = serde_json_core::from_str::<JsonRpc<Option<&str>>>(res) {
-					if json_res.id == json_req.id {
-						if let Some(result) = json_res.result {
-							return Ok(result)
-						} else {
-							if let Ok(json_err) = serde_json_core::from_str::<JsonErrorResponse>(res) {
-								if let Some(error) = json_err.error {
-									if let Some(code) = error.code {
-										return Err(RpcError::Json(JsonError::ErrorCode(code)))
-									}
-								}
-							}
-						}
-						Err(RpcError::Json(JsonError::ErrorParsing))
-					} else {
-						Err(RpcError::ResponseDoesNotMatch)
-					}
-				} else {
-					// The response is not a JsonRpc,
-					// at the moment, let's return the whole response struct
-					// to better analyze the result
-					Ok(res)
-				}
-			}
-			Err(e) => {
-				Err(e)
-			}
+
+		Ok((json_req.id, req_len))
+	}
+
+	/// Parses the result of the response located in `out_buf[..msg_len]`
+	fn parse_response<'s, R: Deserialize<'s>>(&'s self, id: usize, msg_len: usize) -> Result<R, RpcError> {
+		let res = core::str::from_utf8(&self.out_buf[..msg_len])?;
+		parse_result(res, id)
+	}
+}
+
+/// Parses the result of the response `res` to request `id` as `R`,
+/// see [`Rpc::rpc_method`](struct.Rpc.html#method.rpc_method).
+fn parse_result<'r, R: Deserialize<'r>>(res: &'r str, id: usize) -> Result<R, RpcError> {
+	if let Ok((JsonId { id: Some(res_id) }, _)) = serde_json_core::from_str::<JsonId>(res) {
+		if res_id != id {
+			return Err(RpcError::ResponseDoesNotMatch)
 		}
 	}
+
+	if let Some(error) = parse_error(res) {
+		return Err(RpcError::Json(JsonError::ErrorCode(error)))
+	}
+
+	match serde_json_core::from_str::<JsonResult<R>>(res) {
+		Ok((JsonResult { result: Some(result) }, _)) => Ok(result),
+		// `null` result, e.g. empty storage
+		Ok((JsonResult { result: None }, _)) => serde_json_core::from_str::<R>("null")
+			.map(|(result, _)| result)
+			.map_err(|_| RpcError::Json(JsonError::ErrorParsing)),
+		Err(_) => Err(RpcError::Json(JsonError::ErrorParsing)),
+	}
+}
+
+/// Parses the error object of the response, with its data if it's a string
+fn parse_error(res: &str) -> Option<NodeError> {
+	if let Ok((json_err, _)) = serde_json_core::from_str::<JsonErrorResponse>(res) {
+		let error = json_err.error?;
+		return error.code.map(|code| NodeError::new(code, error.message, error.data))
+	}
+
+	let (json_err, _) = serde_json_core::from_str::<JsonErrorCodeResponse>(res).ok()?;
+	let error = json_err.error?;
+	error.code.map(|code| NodeError::new(code, error.message, None))
 }
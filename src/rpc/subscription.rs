@@ -0,0 +1,83 @@
+use heapless::{String, consts::*};
+use serde::Deserialize;
+use crate::rpc::{RpcError, JsonError, parse_result};
+
+/// Id of a subscription, given by the node as a string or a number depending on its version
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubscriptionId {
+	Str(String<U32>),
+	Num(u64),
+}
+
+/// Notification sent by the node for a subscription:
+/// `{"jsonrpc":"2.0","method":"state_storage","params":{"subscription":"...","result":...}}`
+#[derive(Deserialize)]
+struct JsonNotification<'a, P> {
+	method: &'a str,
+	params: P,
+}
+
+#[derive(Deserialize)]
+struct StrSubscription<'a> {
+	subscription: &'a str,
+}
+
+#[derive(Deserialize)]
+struct NumSubscription {
+	subscription: u64,
+}
+
+#[derive(Deserialize)]
+struct NotificationResult<R> {
+	result: R,
+}
+
+/// Only notifications have a `method`, responses don't
+#[derive(Deserialize)]
+struct JsonMethod<'a> {
+	method: Option<&'a str>,
+}
+
+/// Parses the response `res` to the subscription request `id`, containing the subscription id
+///
+/// # Errors
+/// * `Json(ErrorParsing)`: the subscription id is neither a string nor a number, or is too long
+/// * any error returned by the node
+pub(crate) fn parse_id(res: &str, id: usize) -> Result<SubscriptionId, RpcError> {
+	match parse_result::<&str>(res, id) {
+		Ok(sub_id) => {
+			let mut s = String::new();
+			s.push_str(sub_id).map_err(|_| RpcError::Json(JsonError::ErrorParsing))?;
+			Ok(SubscriptionId::Str(s))
+		}
+		Err(RpcError::Json(JsonError::ErrorParsing)) => parse_result::<u64>(res, id).map(SubscriptionId::Num),
+		Err(e) => Err(e),
+	}
+}
+
+/// Returns true if `msg` is a notification, whatever the subscription
+pub(crate) fn is_notification(msg: &[u8]) -> bool {
+	matches!(serde_json_core::from_slice::<JsonMethod>(msg), Ok((JsonMethod { method: Some(_) }, _)))
+}
+
+/// Returns true if `msg` is a notification `method` for the subscription `id`
+pub(crate) fn is_notification_for(msg: &[u8], method: &str, id: &SubscriptionId) -> bool {
+	match id {
+		SubscriptionId::Str(id) => matches!(
+			serde_json_core::from_slice::<JsonNotification<StrSubscription>>(msg),
+			Ok((n, _)) if n.method == method && n.params.subscription == id.as_str()),
+		SubscriptionId::Num(id) => matches!(
+			serde_json_core::from_slice::<JsonNotification<NumSubscription>>(msg),
+			Ok((n, _)) if n.method == method && n.params.subscription == *id),
+	}
+}
+
+/// Parses the `result` of the notification `res` as `R`
+///
+/// # Errors
+/// * `Json(ErrorParsing)`: the result cannot be deserialized as `R`
+pub(crate) fn parse_notification<'r, R: Deserialize<'r>>(res: &'r str) -> Result<R, RpcError> {
+	serde_json_core::from_str::<JsonNotification<NotificationResult<R>>>(res)
+		.map(|(notification, _)| notification.params.result)
+		.map_err(|_| RpcError::Json(JsonError::ErrorParsing))
+}
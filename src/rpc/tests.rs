@@ -0,0 +1,471 @@
+use crate::rpc::{Rpc, RpcError, ReconnectPolicy, Endpoint, Scheme, BatchRequest};
+use crate::rpc::batch::{self, JsonObjects};
+use crate::rpc::subscription::{self, SubscriptionId};
+use crate::rpc::{parse_result, JsonError, AuthorError, InvalidTransaction, HttpError};
+use crate::rpc::http;
+use crate::TcpError;
+use embedded_nal::nb;
+use crate::rpc::mock::{MockState, MockTransport, MockClock, MockError, Step, frame, text, http_response, buffers};
+use serde::Deserialize;
+
+#[test]
+fn test_backoff() {
+	let policy = ReconnectPolicy {
+		max_retries: 3,
+		initial_backoff_ms: 500,
+		max_backoff_ms: 3000,
+	};
+
+	assert_eq!(policy.backoff_ms(0), 500);
+	assert_eq!(policy.backoff_ms(1), 1000);
+	assert_eq!(policy.backoff_ms(2), 2000);
+	assert_eq!(policy.backoff_ms(3), 3000);
+	assert_eq!(policy.backoff_ms(200), 3000);
+}
+
+#[test]
+fn test_endpoint_parse() {
+	let endpoint = Endpoint::parse("ws://127.0.0.1:9944").unwrap();
+	assert_eq!(endpoint.host, "127.0.0.1");
+	assert_eq!(endpoint.port, 9944);
+	assert_eq!(endpoint.path, "/");
+	assert_eq!(endpoint.host_header().unwrap().as_str(), "127.0.0.1:9944");
+	assert_eq!(endpoint.origin_header().unwrap().as_str(), "http://127.0.0.1:9944");
+
+	let endpoint = Endpoint::parse("wss://rpc.example.com/ws").unwrap();
+	assert_eq!(endpoint.scheme, Scheme::Wss);
+	assert_eq!(endpoint.port, 443);
+	assert_eq!(endpoint.origin_header().unwrap().as_str(), "https://rpc.example.com:443");
+
+	let endpoint = Endpoint::parse("10.0.0.1/ws/api-key").unwrap();
+	assert_eq!(endpoint.scheme, Scheme::Ws);
+	assert_eq!(endpoint.host, "10.0.0.1");
+	assert_eq!(endpoint.port, 80);
+	assert_eq!(endpoint.path, "/ws/api-key");
+
+	let endpoint = Endpoint::parse("[::1]:9944/ws").unwrap();
+	assert_eq!(endpoint.host, "::1");
+	assert_eq!(endpoint.port, 9944);
+	assert_eq!(endpoint.host_header().unwrap().as_str(), "[::1]:9944");
+
+	let endpoint = Endpoint::parse("https://gateway.example.com/rpc").unwrap();
+	assert_eq!(endpoint.scheme, Scheme::Https);
+	assert_eq!(endpoint.port, 443);
+	assert!(endpoint.is_http());
+
+	assert!(Endpoint::parse("ws://:9944").is_err());
+	assert!(Endpoint::parse("127.0.0.1:99440").is_err());
+}
+
+#[test]
+fn test_http_request_head() {
+	let headers = ["Authorization: Bearer key"];
+	let endpoint = Endpoint {
+		headers: Some(&headers),
+		..Endpoint::parse("http://10.0.0.1:9933/rpc").unwrap()
+	};
+
+	let mut buf = [0_u8; 256];
+	let len = http::write_request_head(&endpoint, 42, &mut buf).unwrap();
+	assert_eq!(&buf[..len], &b"POST /rpc HTTP/1.1\r\nHost: 10.0.0.1:9933\r\nContent-Type: application/json\r\n\
+		Content-Length: 42\r\nConnection: close\r\nAuthorization: Bearer key\r\n\r\n"[..]);
+
+	assert!(http::write_request_head(&endpoint, 42, &mut buf[..32]).is_err());
+}
+
+#[test]
+fn test_http_response() {
+	assert!(http::parse_response_head(b"HTTP/1.1 200 OK\r\nContent-Length: 2").unwrap().is_none());
+
+	let response = b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\n{}";
+	let head = http::parse_response_head(response).unwrap().unwrap();
+	assert_eq!(head.status, 200);
+	assert_eq!(head.len, response.len() - 2);
+	assert_eq!(head.content_length, Some(2));
+	assert!(!head.chunked);
+
+	let head = http::parse_response_head(b"HTTP/1.1 502 Bad Gateway\r\nTransfer-Encoding: chunked\r\n\r\n")
+		.unwrap().unwrap();
+	assert_eq!(head.status, 502);
+	assert!(head.chunked);
+
+	let mut body = *b"5\r\n{\"id\"\r\n3;ext=1\r\n:1}\r\n0\r\n\r\n";
+	let len = http::decode_chunked(&mut body).unwrap();
+	assert_eq!(&body[..len], &b"{\"id\":1}"[..]);
+
+	let mut incomplete = *b"a\r\n{}";
+	assert!(http::decode_chunked(&mut incomplete).is_err());
+}
+
+#[test]
+fn test_call_over_mock_transport() {
+	buffers!(buffers, 256);
+	let state = MockState::new();
+	state.borrow_mut().receive(Step::Data(b"HTTP/1.1 200 OK\r\n\r\n{\"jsonrpc\":\"2.0\",\"result\":\"leger\",\"id\":1}".to_vec()));
+	state.borrow_mut().receive(Step::Closed);
+	let endpoint = Endpoint::parse("http://127.0.0.1:9933").unwrap();
+
+	let mut rpc = Rpc::new(MockTransport::new(&state), buffers).unwrap();
+	assert_eq!(rpc.call::<(), &str>(&endpoint, true, Some("system_name"), None).unwrap(), "leger");
+	assert!(!rpc.is_connected());
+}
+
+#[test]
+fn test_http_error_status() {
+	buffers!(buffers, 256);
+	// the body is larger than `out_buf`
+	let mut response = b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 400\r\n\r\n".to_vec();
+	response.extend_from_slice(&[b'x'; 400]);
+	let state = MockState::new();
+	state.borrow_mut().receive(Step::Data(response));
+	let endpoint = Endpoint::parse("http://127.0.0.1:9933").unwrap();
+
+	let mut rpc = Rpc::new(MockTransport::new(&state), buffers).unwrap();
+	assert!(matches!(rpc.call::<(), &str>(&endpoint, false, Some("system_name"), None),
+		Err(RpcError::Http(HttpError::Status(503)))));
+	assert!(!rpc.is_connected());
+}
+
+#[test]
+fn test_transport_error() {
+	buffers!(buffers, 64);
+	let state = MockState::new();
+	state.borrow_mut().refuse = true;
+	let endpoint = Endpoint::parse("ws://127.0.0.1:9944").unwrap();
+
+	let mut rpc = Rpc::new(MockTransport::new(&state), buffers).unwrap();
+	assert!(rpc.transport_error().is_none());
+	assert!(matches!(rpc.call::<(), &str>(&endpoint, true, Some("system_name"), None), Err(RpcError::Transport)));
+	assert_eq!(rpc.transport_error(), Some(&MockError));
+}
+
+#[test]
+fn test_batch_serialize() {
+	let requests = [
+		BatchRequest::new("chain_getBlockHash", None),
+		BatchRequest::new("chain_getBlockHash", Some("[0]")),
+	];
+
+	let mut buf = [0_u8; 128];
+	let len = batch::serialize(&requests, 7, &mut buf).unwrap();
+	assert_eq!(&buf[..len], &b"[{\"id\":7,\"jsonrpc\":\"2.0\",\"method\":\"chain_getBlockHash\"},\
+		{\"id\":8,\"jsonrpc\":\"2.0\",\"method\":\"chain_getBlockHash\",\"params\":[0]}]"[..]);
+
+	assert!(batch::serialize(&requests, 7, &mut buf[..64]).is_err());
+}
+
+#[test]
+fn test_json_objects() {
+	let mut objects = JsonObjects::new("[{\"a\":{\"b\":1}},{\"c\":\"}{\\\"\"}]");
+	assert_eq!(objects.next(), Some("{\"a\":{\"b\":1}}"));
+	assert_eq!(objects.next(), Some("{\"c\":\"}{\\\"\"}"));
+	assert_eq!(objects.next(), None);
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpecVersion {
+	spec_version: u32,
+}
+
+#[test]
+fn test_batch_over_mock_transport() {
+	buffers!(buffers, 256);
+	// responses can be received in any order
+	let state = MockState::new();
+	state.borrow_mut().receive(http_response("[{\"jsonrpc\":\"2.0\",\"result\":{\"specVersion\":3},\"id\":2},\
+		{\"jsonrpc\":\"2.0\",\"result\":\"0xab\",\"id\":1}]"));
+	let endpoint = Endpoint::parse("http://127.0.0.1:9933").unwrap();
+	let requests = [
+		BatchRequest::new("chain_getBlockHash", None),
+		BatchRequest::new("state_getRuntimeVersion", None),
+	];
+
+	let mut rpc = Rpc::new(MockTransport::new(&state), buffers).unwrap();
+	let batch = rpc.call_batch(&endpoint, true, &requests).unwrap();
+	assert_eq!(batch.result::<&str>(0).unwrap(), "0xab");
+	assert_eq!(batch.result::<SpecVersion>(1).unwrap().spec_version, 3);
+	assert!(batch.result::<&str>(2).is_err());
+}
+
+#[test]
+fn test_typed_result() {
+	assert_eq!(parse_result::<u32>("{\"jsonrpc\":\"2.0\",\"result\":5,\"id\":1}", 1).unwrap(), 5);
+	assert_eq!(parse_result::<[u8; 2]>("{\"jsonrpc\":\"2.0\",\"result\":[1,2],\"id\":1}", 1).unwrap(), [1, 2]);
+
+	let null = "{\"jsonrpc\":\"2.0\",\"result\":null,\"id\":2}";
+	assert_eq!(parse_result::<Option<&str>>(null, 2).unwrap(), None);
+	assert!(matches!(parse_result::<&str>(null, 2), Err(RpcError::Json(JsonError::ErrorParsing))));
+	assert!(matches!(parse_result::<Option<&str>>(null, 3), Err(RpcError::ResponseDoesNotMatch)));
+}
+
+#[test]
+fn test_node_error() {
+	let res = "{\"jsonrpc\":\"2.0\",\"error\":{\"code\":1010,\"message\":\"Invalid Transaction\",\
+		\"data\":\"Inability to pay some fees (e.g. account balance too low)\"},\"id\":4}";
+	match parse_result::<&str>(res, 4) {
+		Err(RpcError::Json(JsonError::ErrorCode(error))) => {
+			assert_eq!(error.code, 1010);
+			assert_eq!(error.message.as_str(), "Invalid Transaction");
+			assert_eq!(error.author_error(), Some(AuthorError::InvalidTransaction(InvalidTransaction::Payment)));
+		}
+		_ => panic!("error not parsed"),
+	}
+
+	// message sent by the node, as is
+	let res = "{\"jsonrpc\":\"2.0\",\"error\":{\"code\":1010,\"message\":\"Invalid Transaction\",\
+		\"data\":\"Transaction would exhausts the block limits\"},\"id\":4}";
+	match parse_result::<&str>(res, 4) {
+		Err(RpcError::Json(JsonError::ErrorCode(error))) => {
+			assert_eq!(error.author_error(),
+				Some(AuthorError::InvalidTransaction(InvalidTransaction::ExhaustsResources)));
+		}
+		_ => panic!("error not parsed"),
+	}
+
+	// data is truncated, not a string or missing
+	let res = "{\"jsonrpc\":\"2.0\",\"error\":{\"code\":1010,\"message\":\"Invalid Transaction\",\
+		\"data\":\"Transaction is outdated, and this is a really long explanation of why it is outdated\"},\"id\":5}";
+	match parse_result::<&str>(res, 5) {
+		Err(RpcError::Json(JsonError::ErrorCode(error))) => {
+			assert_eq!(error.data.unwrap().len(), 64);
+		}
+		_ => panic!("error not parsed"),
+	}
+
+	let res = "{\"jsonrpc\":\"2.0\",\"error\":{\"code\":1014,\"message\":\"Priority is too low\",\
+		\"data\":{\"old\":1}},\"id\":6}";
+	match parse_result::<&str>(res, 6) {
+		Err(RpcError::Json(JsonError::ErrorCode(error))) => {
+			assert!(error.data.is_none());
+			assert_eq!(error.author_error(), Some(AuthorError::TooLowPriority));
+		}
+		_ => panic!("error not parsed"),
+	}
+
+	let res = "{\"jsonrpc\":\"2.0\",\"error\":{\"code\":-32601,\"message\":\"Method not found\"},\"id\":7}";
+	match parse_result::<&str>(res, 7) {
+		Err(RpcError::Json(JsonError::ErrorCode(error))) => {
+			assert_eq!(error.code, -32601);
+			assert!(error.author_error().is_none());
+		}
+		_ => panic!("error not parsed"),
+	}
+}
+
+#[test]
+fn test_subscription() {
+	let id = subscription::parse_id("{\"jsonrpc\":\"2.0\",\"result\":\"Ac2Tfqk9sYSzS9Ra\",\"id\":3}", 3).unwrap();
+	assert_eq!(id, SubscriptionId::Str("Ac2Tfqk9sYSzS9Ra".into()));
+	// older nodes use numbers
+	let num_id = subscription::parse_id("{\"jsonrpc\":\"2.0\",\"result\":42,\"id\":4}", 4).unwrap();
+	assert_eq!(num_id, SubscriptionId::Num(42));
+
+	let notification = "{\"jsonrpc\":\"2.0\",\"method\":\"state_storage\",\
+		\"params\":{\"subscription\":\"Ac2Tfqk9sYSzS9Ra\",\"result\":7}}";
+	assert!(subscription::is_notification(notification.as_bytes()));
+	assert!(subscription::is_notification_for(notification.as_bytes(), "state_storage", &id));
+	assert!(!subscription::is_notification_for(notification.as_bytes(), "state_storage", &num_id));
+	assert!(!subscription::is_notification_for(notification.as_bytes(), "chain_newHead", &id));
+	assert_eq!(subscription::parse_notification::<u32>(notification).unwrap(), 7);
+
+	// responses are not notifications
+	assert!(!subscription::is_notification(b"{\"jsonrpc\":\"2.0\",\"result\":true,\"id\":5}"));
+}
+
+#[test]
+fn test_websocket_handshake() {
+	buffers!(buffers, 256);
+	// the response head is received in two reads
+	let state = MockState::new();
+	state.borrow_mut().split_handshake = true;
+	state.borrow_mut().receive(text("{\"jsonrpc\":\"2.0\",\"result\":\"leger\",\"id\":1}"));
+	let endpoint = Endpoint::parse("ws://127.0.0.1:9944").unwrap();
+
+	let mut rpc = Rpc::new(MockTransport::new(&state), buffers).unwrap();
+	assert_eq!(rpc.call::<(), &str>(&endpoint, true, Some("system_name"), None).unwrap(), "leger");
+	assert!(rpc.is_connected());
+	assert_eq!(state.borrow().texts(), ["{\"id\":1,\"jsonrpc\":\"2.0\",\"method\":\"system_name\"}"]);
+}
+
+#[test]
+fn test_continuation_frames() {
+	buffers!(buffers, 256);
+	// a ping is received between the two frames of the response
+	let state = MockState::new();
+	state.borrow_mut().receive(Step::Data(frame(false, 1, b"{\"jsonrpc\":\"2.0\",")));
+	state.borrow_mut().receive(Step::Data(frame(true, 9, b"beat")));
+	state.borrow_mut().receive(Step::Data(frame(true, 0, b"\"result\":\"leger\",\"id\":1}")));
+	let endpoint = Endpoint::parse("ws://127.0.0.1:9944").unwrap();
+
+	let mut rpc = Rpc::new(MockTransport::new(&state), buffers).unwrap();
+	assert_eq!(rpc.call::<(), &str>(&endpoint, true, Some("system_name"), None).unwrap(), "leger");
+	// answered with a pong
+	assert!(state.borrow().frames.iter().any(|(opcode, payload)| *opcode == 0xa && payload == b"beat"));
+}
+
+#[test]
+fn test_dropped_notifications() {
+	buffers!(buffers, 256);
+	// a notification is received before the response
+	let state = MockState::new();
+	state.borrow_mut().receive(text("{\"jsonrpc\":\"2.0\",\"method\":\"state_storage\",\
+		\"params\":{\"subscription\":\"abc\",\"result\":7}}"));
+	state.borrow_mut().receive(text("{\"jsonrpc\":\"2.0\",\"result\":\"leger\",\"id\":1}"));
+	let endpoint = Endpoint::parse("ws://127.0.0.1:9944").unwrap();
+
+	let mut rpc = Rpc::new(MockTransport::new(&state), buffers).unwrap();
+	assert!(!rpc.take_dropped_notifications());
+	assert_eq!(rpc.call::<(), &str>(&endpoint, true, Some("system_name"), None).unwrap(), "leger");
+	assert!(rpc.take_dropped_notifications());
+	assert!(!rpc.take_dropped_notifications());
+}
+
+#[test]
+fn test_message_too_large() {
+	buffers!(buffers, 160, 256, 256);
+	let state = MockState::new();
+	state.borrow_mut().receive(Step::Data(frame(true, 1, &[b' '; 300])));
+	let endpoint = Endpoint::parse("ws://127.0.0.1:9944").unwrap();
+
+	let mut rpc = Rpc::new(MockTransport::new(&state), buffers).unwrap();
+	assert!(matches!(rpc.call::<(), &str>(&endpoint, false, Some("system_name"), None), Err(RpcError::MessageTooLarge)));
+	// the rest of the frame cannot be read
+	assert!(!rpc.is_connected());
+}
+
+#[test]
+fn test_timeout_in_message() {
+	let clock = MockClock::new(10);
+	buffers!(buffers, 256);
+	// the end of the response never comes
+	let state = MockState::new();
+	state.borrow_mut().receive(Step::Data(frame(false, 1, b"{\"jsonrpc\":\"2.0\",")));
+	let endpoint = Endpoint::parse("ws://127.0.0.1:9944").unwrap();
+
+	let mut rpc = Rpc::new(MockTransport::new(&state), buffers).unwrap();
+	rpc.set_clock(&clock, 100);
+	assert!(matches!(rpc.call::<(), &str>(&endpoint, false, Some("system_name"), None), Err(RpcError::Timeout)));
+	assert!(!rpc.is_connected());
+
+	// the next response is read from a new connection
+	state.borrow_mut().receive(text("{\"jsonrpc\":\"2.0\",\"result\":\"leger\",\"id\":2}"));
+	assert_eq!(rpc.call::<(), &str>(&endpoint, false, Some("system_name"), None).unwrap(), "leger");
+}
+
+#[test]
+fn test_request_after_timeout() {
+	let clock = MockClock::new(10);
+	buffers!(buffers, 256);
+	let state = MockState::new();
+	let endpoint = Endpoint::parse("ws://127.0.0.1:9944").unwrap();
+
+	let mut rpc = Rpc::new(MockTransport::new(&state), buffers).unwrap();
+	rpc.set_clock(&clock, 100);
+	assert!(matches!(rpc.call::<(), &str>(&endpoint, false, Some("author_submitExtrinsic"), None),
+		Err(RpcError::Timeout)));
+	// the response to the first request will never be read
+	assert!(!rpc.is_connected());
+
+	state.borrow_mut().receive(text("{\"jsonrpc\":\"2.0\",\"result\":\"leger\",\"id\":2}"));
+	assert_eq!(rpc.call::<(), &str>(&endpoint, false, Some("system_name"), None).unwrap(), "leger");
+	assert_eq!(state.borrow().connections, 2);
+}
+
+#[test]
+fn test_connection_lost() {
+	assert!(RpcError::ConnectionClosed.is_connection_lost());
+	assert!(RpcError::Timeout.is_connection_lost());
+	assert!(RpcError::TcpSocket(TcpError::CountNotMatching).is_connection_lost());
+	assert!(!RpcError::TcpSocket(TcpError::InvalidAddress).is_connection_lost());
+	assert!(!RpcError::TcpSocket(TcpError::CannotResolve).is_connection_lost());
+	assert!(!RpcError::Embedded(nb::Error::WouldBlock).is_connection_lost());
+	assert!(!RpcError::MessageTooLarge.is_connection_lost());
+	// decided by the transport
+	assert!(!RpcError::Transport.is_connection_lost());
+}
+
+#[test]
+fn test_handshake_failure() {
+	buffers!(buffers, 256);
+	let state = MockState::new();
+	state.borrow_mut().refuse_handshake = true;
+	let endpoint = Endpoint::parse("ws://127.0.0.1:9944").unwrap();
+
+	let mut rpc = Rpc::new(MockTransport::new(&state), buffers).unwrap();
+	rpc.set_reconnect_policy(ReconnectPolicy {
+		max_retries: 0,
+		..ReconnectPolicy::default()
+	});
+	assert!(matches!(rpc.call::<(), &str>(&endpoint, true, Some("system_name"), None), Err(RpcError::WebSocket(_))));
+	// the transport has been closed, and can be connected again
+	assert!(!state.borrow().connected);
+
+	state.borrow_mut().refuse_handshake = false;
+	state.borrow_mut().receive(text("{\"jsonrpc\":\"2.0\",\"result\":\"leger\",\"id\":2}"));
+	assert_eq!(rpc.call::<(), &str>(&endpoint, true, Some("system_name"), None).unwrap(), "leger");
+	assert_eq!(state.borrow().connections, 2);
+}
+
+#[test]
+fn test_unrecoverable_transport_error() {
+	buffers!(buffers, 256);
+	let state = MockState::new();
+	state.borrow_mut().refuse = true;
+	state.borrow_mut().unrecoverable = true;
+	let endpoint = Endpoint::parse("ws://127.0.0.1:9944").unwrap();
+
+	let mut rpc = Rpc::new(MockTransport::new(&state), buffers).unwrap();
+	assert!(matches!(rpc.call::<(), &str>(&endpoint, true, Some("system_name"), None), Err(RpcError::Transport)));
+	assert!(!rpc.is_connection_lost(&RpcError::Transport));
+	// not retried
+	assert_eq!(state.borrow().attempts, 1);
+
+	state.borrow_mut().unrecoverable = false;
+	assert!(matches!(rpc.call::<(), &str>(&endpoint, true, Some("system_name"), None), Err(RpcError::Transport)));
+	assert!(rpc.is_connection_lost(&RpcError::Transport));
+	// retried 3 times by default
+	assert_eq!(state.borrow().attempts, 5);
+}
+
+#[test]
+fn test_reconnect() {
+	buffers!(buffers, 256);
+	// the connection is closed by the node before the first response
+	let state = MockState::new();
+	state.borrow_mut().receive(Step::Closed);
+	state.borrow_mut().receive(text("{\"jsonrpc\":\"2.0\",\"result\":\"leger\",\"id\":1}"));
+	state.borrow_mut().receive(Step::Closed);
+	let endpoint = Endpoint::parse("ws://127.0.0.1:9944").unwrap();
+
+	let mut rpc = Rpc::new(MockTransport::new(&state), buffers).unwrap();
+	assert_eq!(rpc.call::<(), &str>(&endpoint, true, Some("system_name"), None).unwrap(), "leger");
+	assert_eq!(state.borrow().connections, 2);
+	assert_eq!(rpc.connections(), 2);
+
+	// requests which are not idempotent are not sent again
+	assert!(matches!(rpc.call::<(), &str>(&endpoint, false, Some("author_submitExtrinsic"), None),
+		Err(RpcError::ConnectionClosed)));
+	assert_eq!(state.borrow().connections, 2);
+	assert_eq!(state.borrow().texts().len(), 3);
+}
+
+#[test]
+fn test_no_reconnect() {
+	buffers!(buffers, 256);
+	let state = MockState::new();
+	let mut rpc = Rpc::new(MockTransport::new(&state), buffers).unwrap();
+
+	// nothing received and no clock to wait for the response, which is not waited for anymore
+	let endpoint = Endpoint::parse("ws://127.0.0.1:9944").unwrap();
+	assert!(matches!(rpc.call::<(), &str>(&endpoint, true, Some("system_name"), None),
+		Err(RpcError::Embedded(nb::Error::WouldBlock))));
+	assert!(!rpc.is_connected());
+	assert_eq!(state.borrow().connections, 1);
+
+	// hostname without resolver
+	let endpoint = Endpoint::parse("ws://node.example.com:9944").unwrap();
+	assert!(matches!(rpc.call::<(), &str>(&endpoint, true, Some("system_name"), None),
+		Err(RpcError::TcpSocket(TcpError::InvalidAddress))));
+	assert_eq!(state.borrow().connections, 1);
+}
@@ -2,6 +2,12 @@ mod tests;
 
 pub trait Compact {
 	fn scale_compact(&self, payload: &mut [u8]) -> usize;
+
+	/// Number of bytes written by `scale_compact`
+	fn scale_compact_size(&self) -> usize {
+		let mut payload = [0_u8; 17];
+		self.scale_compact(&mut payload)
+	}
 }
 
 impl Compact for u32 {
@@ -13,14 +19,14 @@ impl Compact for u32 {
 			i.iter().zip(payload.iter_mut())
 				.for_each(|(f, t)| *t = *f);
 			i.len()
-		} else if *self < (2_u32.pow(14) - 1) {
+		} else if *self < 2_u32.pow(14) {
 			let casted = ((*self << 2) + 1) as u16;
 			let i = casted.to_le_bytes();
 
 			i.iter().zip(payload.iter_mut())
 				.for_each(|(f, t)| *t = *f);
 			i.len()
-		} else if *self < (2_u32.pow(30) - 1) {
+		} else if *self < 2_u32.pow(30) {
 			let i = ((*self << 2) + 2).to_le_bytes();
 
 			i.iter().zip(payload.iter_mut())
@@ -36,7 +42,7 @@ impl Compact for u32 {
 impl Compact for u128 {
 	fn scale_compact(&self, payload: &mut [u8]) -> usize {
 		// check if goes into a u32
-		if *self < (2_u32.pow(30) - 1) as u128 {
+		if *self < 2_u32.pow(30) as u128 {
 			let casted = *self as u32;
 			return casted.scale_compact(payload)
 		} else {
@@ -28,4 +28,22 @@ fn test_scale_compact(){
 	number_u32 = 16384_u32;
 	count = number_u32.scale_compact(&mut payload);
 	assert_eq!(count, 4);
-}
\ No newline at end of file
+
+	// upper bounds of each mode
+	number_u32 = 63_u32;
+	count = number_u32.scale_compact(&mut payload);
+	assert_eq!(payload[..count], [0xFC]);
+
+	number_u32 = 16383_u32;
+	count = number_u32.scale_compact(&mut payload);
+	assert_eq!(payload[..count], [0xFD, 0xFF]);
+
+	number_u32 = 1073741823_u32;
+	count = number_u32.scale_compact(&mut payload);
+	assert_eq!(payload[..count], [0xFE, 0xFF, 0xFF, 0xFF]);
+
+	number_u32 = 1073741824_u32;
+	count = number_u32.scale_compact(&mut payload);
+	assert_eq!(payload[..count], [3, 0, 0, 0, 64]);
+	assert_eq!(number_u32.scale_compact_size(), 5);
+}
@@ -0,0 +1,241 @@
+use core::fmt;
+use core::marker::PhantomData;
+use heapless::{Vec, consts::*};
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::ser::SerializeSeq;
+use serde::de::{Visitor, SeqAccess};
+use crate::hashing::{twox_128, StorageHasher};
+use crate::ProviderError;
+
+#[cfg(test)]
+mod tests;
+
+/// Maximum size of a storage key, e.g. pallet and item prefixes followed by two account IDs
+/// hashed with `Blake2_128Concat`
+pub const MAX_STORAGE_KEY_SIZE: usize = 128;
+/// Size of the largest storage key encoded as an hex string: "0x" + `MAX_STORAGE_KEY_SIZE` bytes
+pub const MAX_STORAGE_KEY_HEX_SIZE: usize = 2 + MAX_STORAGE_KEY_SIZE * 2;
+/// Maximum number of values kept from a `state_queryStorageAt` response
+pub const MAX_STORAGE_CHANGES: usize = 8;
+
+/// Key of a storage value or of an entry of a storage map:
+/// `twox_128(pallet) ++ twox_128(item) ++ hasher(key) [++ hasher(key2)]`
+#[derive(Debug, Clone)]
+pub struct StorageKey {
+	key: Vec<u8, U128>,
+}
+
+impl StorageKey {
+	/// Key of the storage value `item` of `pallet` (e.g. `Timestamp`, `Now`), or prefix of the
+	/// storage map `item`
+	pub fn new(pallet: &str, item: &str) -> StorageKey {
+		let mut key = Vec::new();
+		// 32 bytes always fit
+		let _ = key.extend_from_slice(&twox_128(pallet.as_bytes()));
+		let _ = key.extend_from_slice(&twox_128(item.as_bytes()));
+
+		StorageKey {
+			key,
+		}
+	}
+
+	/// Appends the SCALE-encoded `key` of a storage map, hashed with `hasher` as declared in the
+	/// pallet. Called twice for double maps.
+	///
+	/// ## Errors
+	/// * `InvalidSize`: the storage key would be longer than `MAX_STORAGE_KEY_SIZE`, the needed size
+	/// is returned
+	pub fn push_key(&mut self, hasher: StorageHasher, key: &[u8]) -> Result<(), ProviderError> {
+		let start = self.key.len();
+		let size = start + hasher.hashed_len(key);
+		if size > MAX_STORAGE_KEY_SIZE {
+			return Err(ProviderError::InvalidSize(size))
+		}
+
+		let _ = self.key.resize_default(size);
+		hasher.hash_into(key, &mut self.key[start..]);
+
+		Ok(())
+	}
+
+	pub fn as_bytes(&self) -> &[u8] {
+		&self.key
+	}
+
+	/// Writes the key into `buf` as an hex string starting with "0x", as expected by `state_*` methods
+	///
+	/// ## Errors
+	/// * `InvalidSize`: `buf` isn't large enough, the needed size is returned
+	pub fn write_hex<'b>(&self, buf: &'b mut [u8]) -> Result<&'b str, ProviderError> {
+		let size = 2 + self.key.len() * 2;
+		if buf.len() < size {
+			return Err(ProviderError::InvalidSize(size))
+		}
+
+		buf[..2].copy_from_slice(b"0x");
+		hex::encode_to_slice(self.as_bytes(), &mut buf[2..size])
+			.map_err(|_| ProviderError::CannotParse)?;
+
+		core::str::from_utf8(&buf[..size]).map_err(|_| ProviderError::CannotParse)
+	}
+}
+
+/// Parameters of `state_queryStorageAt`: the keys as hex strings, and the block hash if given
+pub(crate) struct QueryParams<'k> {
+	pub(crate) keys: &'k [StorageKey],
+	pub(crate) at: Option<&'k str>,
+}
+
+struct HexKeys<'k>(&'k [StorageKey]);
+
+impl Serialize for QueryParams<'_> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let mut seq = serializer.serialize_seq(None)?;
+		seq.serialize_element(&HexKeys(self.keys))?;
+		if let Some(at) = self.at {
+			seq.serialize_element(at)?;
+		}
+		seq.end()
+	}
+}
+
+impl Serialize for HexKeys<'_> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+		for key in self.0 {
+			// keys are encoded one at a time, the largest one always fits
+			let mut buf = [0_u8; MAX_STORAGE_KEY_HEX_SIZE];
+			seq.serialize_element(key.write_hex(&mut buf).unwrap_or("0x"))?;
+		}
+		seq.end()
+	}
+}
+
+/// Value of a storage key returned by `state_queryStorageAt`, both as hex strings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StorageChange<'a> {
+	pub key: &'a str,
+	/// `None` if the storage is empty
+	pub value: Option<&'a str>,
+}
+
+/// Values of the storage keys read with
+/// [`Provider::query_storage_at`](../struct.Provider.html#method.query_storage_at), at most
+/// `MAX_STORAGE_CHANGES` are kept.
+#[derive(Debug, Clone)]
+pub struct StorageChanges<'a> {
+	block: &'a str,
+	changes: Vec<StorageChange<'a>, U8>,
+	truncated: bool,
+}
+
+impl<'a> StorageChanges<'a> {
+	/// Hash of the block the values have been read at, as an hex string
+	pub fn block(&self) -> &'a str {
+		self.block
+	}
+
+	pub fn changes(&self) -> &[StorageChange<'a>] {
+		&self.changes
+	}
+
+	/// Returns the value read for `key`, `None` if it's not part of the response
+	pub fn find(&self, key: &StorageKey) -> Option<&StorageChange<'a>> {
+		let mut buf = [0_u8; MAX_STORAGE_KEY_HEX_SIZE];
+		let key = key.write_hex(&mut buf).ok()?;
+
+		self.changes.iter().find(|change| change.key.eq_ignore_ascii_case(key))
+	}
+
+	/// Values have been dropped, more than `MAX_STORAGE_CHANGES` keys have been read
+	pub fn is_truncated(&self) -> bool {
+		self.truncated
+	}
+}
+
+/// Values read at one block: `{"block":"0x...","changes":[["0xkey","0xvalue"],["0xkey",null]]}`,
+/// also sent in `state_storage` notifications
+#[derive(Deserialize)]
+pub(crate) struct ChangeSet<'a> {
+	block: &'a str,
+	changes: ChangeList<'a>,
+}
+
+struct ChangeList<'a> {
+	changes: Vec<StorageChange<'a>, U8>,
+	truncated: bool,
+}
+
+impl<'a> From<ChangeSet<'a>> for StorageChanges<'a> {
+	fn from(set: ChangeSet<'a>) -> StorageChanges<'a> {
+		StorageChanges {
+			block: set.block,
+			changes: set.changes.changes,
+			truncated: set.changes.truncated,
+		}
+	}
+}
+
+struct ChangeListVisitor<'a>(PhantomData<&'a ()>);
+
+impl<'de: 'a, 'a> Visitor<'de> for ChangeListVisitor<'a> {
+	type Value = ChangeList<'a>;
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("a list of storage changes")
+	}
+
+	fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+		let mut list = ChangeList {
+			changes: Vec::new(),
+			truncated: false,
+		};
+		while let Some((key, value)) = seq.next_element::<(&'a str, Option<&'a str>)>()? {
+			if list.changes.push(StorageChange { key, value }).is_err() {
+				list.truncated = true;
+			}
+		}
+
+		Ok(list)
+	}
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for ChangeList<'a> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		deserializer.deserialize_seq(ChangeListVisitor(PhantomData))
+	}
+}
+
+struct StorageChangesVisitor<'a>(PhantomData<&'a ()>);
+
+impl<'de: 'a, 'a> Visitor<'de> for StorageChangesVisitor<'a> {
+	type Value = StorageChanges<'a>;
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("a list of storage change sets")
+	}
+
+	/// Only one change set is returned when reading at a given block, the next ones are skipped
+	fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+		let mut changes = StorageChanges {
+			block: "",
+			changes: Vec::new(),
+			truncated: false,
+		};
+		let mut first = true;
+		while let Some(set) = seq.next_element::<ChangeSet<'a>>()? {
+			if first {
+				changes = set.into();
+				first = false;
+			}
+		}
+
+		Ok(changes)
+	}
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for StorageChanges<'a> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		deserializer.deserialize_seq(StorageChangesVisitor(PhantomData))
+	}
+}
@@ -0,0 +1,45 @@
+use crate::storage::{StorageKey, StorageChanges, QueryParams, MAX_STORAGE_KEY_SIZE};
+use crate::hashing::StorageHasher;
+
+#[test]
+fn test_storage_key() {
+	let mut key = StorageKey::new("System", "Account");
+	key.push_key(StorageHasher::Blake2_128Concat, &[0_u8; 32]).unwrap();
+	assert_eq!(key.as_bytes().len(), 80);
+
+	let mut buf = [0_u8; 162];
+	let hex = key.write_hex(&mut buf).unwrap();
+	assert!(hex.starts_with("0x26aa394eea5630e07c48ae0c9558cef7b99d880ec681799c0cf30e8886371da9"));
+	assert_eq!(hex.len(), 162);
+	assert!(key.write_hex(&mut buf[..100]).is_err());
+
+	// double map, second key too long
+	key.push_key(StorageHasher::Twox64Concat, &[1_u8; 4]).unwrap();
+	assert!(key.push_key(StorageHasher::Identity, &[0_u8; MAX_STORAGE_KEY_SIZE]).is_err());
+	assert_eq!(key.as_bytes().len(), 92);
+}
+
+#[test]
+fn test_query_storage() {
+	let key = StorageKey::new("Timestamp", "Now");
+	let empty = StorageKey::new("Sudo", "Key");
+	let keys = [key.clone(), empty.clone()];
+	let params = QueryParams {
+		keys: &keys,
+		at: Some("0xab"),
+	};
+	let mut buf = [0_u8; 512];
+	let len = serde_json_core::to_slice(&params, &mut buf).unwrap();
+	assert!(buf[..len].starts_with(b"[[\"0xf0c365c3cf59d671eb72da0e7a4113c4"));
+	assert!(buf[..len].ends_with(b"\"],\"0xab\"]"));
+
+	let res = "[{\"block\":\"0xab\",\"changes\":[\
+		[\"0xF0C365C3CF59D671EB72DA0E7A4113C49F1F0515F462CDCF84E0F1D6045DFCBB\",\"0x0100\"],\
+		[\"0x5c0d1176a568c1f92944340dbfed9e9c530ebca703c85910e7164cb7d1c9e47b\",null]]}]";
+	let (changes, _) = serde_json_core::from_str::<StorageChanges>(res).unwrap();
+	assert_eq!(changes.block(), "0xab");
+	assert_eq!(changes.changes().len(), 2);
+	assert_eq!(changes.find(&key).unwrap().value, Some("0x0100"));
+	assert_eq!(changes.find(&empty).unwrap().value, None);
+	assert!(!changes.is_truncated());
+}
@@ -1,11 +1,175 @@
 extern crate std;
 
-// use crate::PolkaProvider;
-//
-// #[test]
-// fn new_test() {
-// 	// Instanciate a TCP server
-// 	// connect to the server
-// 	// send a request, echo the request
-// 	// read back the response.
-// }
\ No newline at end of file
+use std::format;
+use std::string::String;
+use crate::{Provider, ProviderError};
+use crate::extrinsic::ExtrinsicCalls;
+use crate::account::{Account, Key, LegerSigner};
+use crate::rpc::Endpoint;
+use crate::rpc::mock::{MockState, MockTransport, Step, text, http_response, buffers};
+use crate::storage::MAX_STORAGE_KEY_HEX_SIZE;
+
+struct ZeroSigner {}
+
+impl LegerSigner for ZeroSigner {
+	fn get_public(&self) -> Key {
+		[0_u8; 32]
+	}
+
+	fn sign(&self, _payload: &[u8], signature: &mut [u8; 64]) {
+		*signature = [0_u8; 64];
+	}
+}
+
+/// SCALE-encoded account info with nonce `nonce` and free balance `free` (below 256)
+fn account_info_hex(nonce: u8, free: u8) -> String {
+	format!("0x{:02x}00000001000000{:02x}{}", nonce, free, "0".repeat(126))
+}
+
+/// `state_storage` notification of the subscription `id`, with the account info `value`
+fn storage_notification(id: &str, key: &str, value: Option<&str>) -> Step {
+	let value = value.map_or(String::from("null"), |value| format!("\"{}\"", value));
+	text(&format!("{{\"jsonrpc\":\"2.0\",\"method\":\"state_storage\",\"params\":{{\"subscription\":\"{}\",\
+		\"result\":{{\"block\":\"0x01\",\"changes\":[[\"{}\",{}]]}}}}}}", id, key, value))
+}
+
+#[test]
+fn test_poll_account() {
+	buffers!(buffers, 1024);
+	let signer = ZeroSigner {};
+	let mut account = Account::new(&signer);
+	let mut key_hex = [0_u8; MAX_STORAGE_KEY_HEX_SIZE];
+	let key = account.storage_key().write_hex(&mut key_hex).unwrap();
+
+	let state = MockState::new();
+	let endpoint = Endpoint::parse("ws://127.0.0.1:9944").unwrap();
+	let mut provider = Provider::new(MockTransport::new(&state), endpoint, buffers).unwrap();
+	assert!(matches!(provider.poll_account(&mut account), Err(ProviderError::NotSubscribed)));
+
+	state.borrow_mut().receive(text("{\"jsonrpc\":\"2.0\",\"result\":\"sub1\",\"id\":1}"));
+	state.borrow_mut().receive(storage_notification("sub1", key, Some(&account_info_hex(5, 100))));
+	state.borrow_mut().receive(storage_notification("sub1", key, None));
+	provider.subscribe_account(&account).unwrap();
+
+	let info = provider.poll_account(&mut account).unwrap().unwrap();
+	assert_eq!(info.nonce(), 5);
+	assert_eq!(info.free(), 100);
+
+	// the account info is removed from storage
+	let info = provider.poll_account(&mut account).unwrap().unwrap();
+	assert_eq!(info.nonce(), 0);
+	assert_eq!(info.free(), 0);
+
+	// nothing received, and no clock to wait for it
+	assert!(provider.poll_account(&mut account).unwrap().is_none());
+}
+
+#[test]
+fn test_poll_account_after_reconnect() {
+	buffers!(buffers, 1024);
+	let signer = ZeroSigner {};
+	let mut account = Account::new(&signer);
+	let mut key_hex = [0_u8; MAX_STORAGE_KEY_HEX_SIZE];
+	let key = account.storage_key().write_hex(&mut key_hex).unwrap();
+
+	let state = MockState::new();
+	state.borrow_mut().receive(text("{\"jsonrpc\":\"2.0\",\"result\":\"sub1\",\"id\":1}"));
+	let endpoint = Endpoint::parse("ws://127.0.0.1:9944").unwrap();
+	let mut provider = Provider::new(MockTransport::new(&state), endpoint, buffers).unwrap();
+	provider.subscribe_account(&account).unwrap();
+
+	// the connection is lost during another request, which is sent again on a new connection
+	state.borrow_mut().receive(Step::Closed);
+	state.borrow_mut().receive(text("{\"jsonrpc\":\"2.0\",\"result\":\"leger\",\"id\":2}"));
+	assert_eq!(provider.system_name().unwrap(), "leger");
+	assert_eq!(state.borrow().connections, 2);
+
+	// the subscription is made again on the new connection
+	state.borrow_mut().receive(text("{\"jsonrpc\":\"2.0\",\"result\":\"sub2\",\"id\":3}"));
+	state.borrow_mut().receive(storage_notification("sub2", key, Some(&account_info_hex(7, 200))));
+	let info = provider.poll_account(&mut account).unwrap().unwrap();
+	assert_eq!(info.nonce(), 7);
+	assert_eq!(info.free(), 200);
+
+	let subscriptions = state.borrow().texts().iter()
+		.filter(|text| text.contains("\"method\":\"state_subscribeStorage\""))
+		.count();
+	assert_eq!(subscriptions, 2);
+	assert_eq!(state.borrow().connections, 2);
+}
+
+#[test]
+fn test_poll_account_after_dropped_notifications() {
+	buffers!(buffers, 1024);
+	let signer = ZeroSigner {};
+	let mut account = Account::new(&signer);
+	let mut key_hex = [0_u8; MAX_STORAGE_KEY_HEX_SIZE];
+	let key = account.storage_key().write_hex(&mut key_hex).unwrap();
+
+	let state = MockState::new();
+	state.borrow_mut().receive(text("{\"jsonrpc\":\"2.0\",\"result\":\"sub1\",\"id\":1}"));
+	state.borrow_mut().receive(storage_notification("sub1", key, Some(&account_info_hex(5, 100))));
+	let endpoint = Endpoint::parse("ws://127.0.0.1:9944").unwrap();
+	let mut provider = Provider::new(MockTransport::new(&state), endpoint, buffers).unwrap();
+	provider.subscribe_account(&account).unwrap();
+	assert_eq!(provider.poll_account(&mut account).unwrap().unwrap().nonce(), 5);
+
+	// a notification is dropped while waiting for another response
+	state.borrow_mut().receive(storage_notification("sub1", key, Some(&account_info_hex(6, 150))));
+	state.borrow_mut().receive(text("{\"jsonrpc\":\"2.0\",\"result\":\"leger\",\"id\":2}"));
+	assert_eq!(provider.system_name().unwrap(), "leger");
+
+	// the account info cannot be read again: the last known one is not returned as an update
+	assert!(matches!(provider.poll_account(&mut account), Err(ProviderError::RpcError(_))));
+
+	// the connection has been closed, the update is received after subscribing again
+	state.borrow_mut().receive(text("{\"jsonrpc\":\"2.0\",\"result\":\"sub2\",\"id\":4}"));
+	state.borrow_mut().receive(storage_notification("sub2", key, Some(&account_info_hex(6, 150))));
+	let info = provider.poll_account(&mut account).unwrap().unwrap();
+	assert_eq!(info.nonce(), 6);
+	assert_eq!(info.free(), 150);
+}
+
+#[test]
+fn test_watch_account() {
+	buffers!(buffers, 1024);
+	let signer = ZeroSigner {};
+	let mut account = Account::new(&signer);
+	let mut key_hex = [0_u8; MAX_STORAGE_KEY_HEX_SIZE];
+	let key = account.storage_key().write_hex(&mut key_hex).unwrap();
+
+	let state = MockState::new();
+	state.borrow_mut().receive(text("{\"jsonrpc\":\"2.0\",\"result\":\"sub1\",\"id\":1}"));
+	state.borrow_mut().receive(storage_notification("sub1", key, Some(&account_info_hex(1, 10))));
+	state.borrow_mut().receive(storage_notification("sub1", key, Some(&account_info_hex(2, 20))));
+	let endpoint = Endpoint::parse("ws://127.0.0.1:9944").unwrap();
+	let mut provider = Provider::new(MockTransport::new(&state), endpoint, buffers).unwrap();
+	provider.subscribe_account(&account).unwrap();
+
+	// returns once everything received has been handled, instead of spinning
+	let mut updates = 0;
+	provider.watch_account(&mut account, |_| {
+		updates += 1;
+		true
+	}).unwrap();
+	assert_eq!(updates, 2);
+	assert_eq!(account.last_nonce(), 2);
+}
+
+#[test]
+fn test_submit_large_raw_extrinsic() {
+	buffers!(buffers, 1024);
+	let state = MockState::new();
+	state.borrow_mut().receive(http_response("{\"jsonrpc\":\"2.0\",\"result\":\"0x01\",\"id\":1}"));
+	let endpoint = Endpoint::parse("http://127.0.0.1:9933").unwrap();
+	let mut provider = Provider::new(MockTransport::new(&state), endpoint, buffers).unwrap();
+
+	// "0x" + 2 bytes of compact length + 300 bytes, as hex
+	let extrinsic = [0x11_u8; 300];
+	assert!(matches!(provider.submit_raw_extrinsic(&extrinsic), Err(ProviderError::InvalidSize(606))));
+
+	let mut buf = [0_u8; 700];
+	assert_eq!(provider.submit_raw_extrinsic_with_buffer(&extrinsic, &mut buf).unwrap(), "0x01");
+	let param = format!("\"params\":[\"0xb104{}\"]", "11".repeat(300));
+	assert!(state.borrow().sent_text().contains(&param));
+}
@@ -0,0 +1,54 @@
+use embedded_nal::{SocketAddr, nb};
+
+pub use tcp::{TcpTransport, TcpTransportError};
+#[cfg(feature = "std")]
+pub use tcp_stream::{TcpStreamTransport, StreamTimeouts};
+#[cfg(feature = "tls")]
+pub use tls::{TlsTransport, TlsError};
+
+mod tcp;
+#[cfg(feature = "std")]
+mod tcp_stream;
+#[cfg(feature = "tls")]
+mod tls;
+
+#[cfg(all(test, feature = "std"))]
+mod tests;
+
+/// Byte stream used by [`Rpc`](../rpc/struct.Rpc.html) to exchange data with the node.
+///
+/// Adapters are provided for any TCP stack implementing [`embedded_nal::TcpClient`](../../embedded_nal/trait.TcpClient.html)
+/// (see [`TcpTransport`](struct.TcpTransport.html)), for `std::net` with the `std` feature (see `TcpStreamTransport`)
+/// and for TLS over any other transport with the `tls` feature (see `TlsTransport`).
+/// It can be implemented for other links: UART bridges, modems, mock transports...
+///
+/// As for `embedded_nal`, operations which cannot be completed immediately return `WouldBlock`
+/// and are called again, until they complete or the timeout expires.
+pub trait Transport {
+	/// Error of the underlying stack, the last one is kept by the provider
+	/// (see [`Provider::transport_error`](../struct.Provider.html#method.transport_error)).
+	type Error: core::fmt::Debug;
+
+	/// Opens the connection to `remote`. `hostname` is the host of the endpoint, as given by the
+	/// user (used by TLS to check the server certificate).
+	/// A new connection can be opened after `close()` has been called.
+	fn connect(&mut self, remote: SocketAddr, hostname: &str) -> nb::Result<(), Self::Error>;
+
+	/// Sends data, returns the number of bytes actually sent
+	fn send(&mut self, data: &[u8]) -> nb::Result<usize, Self::Error>;
+
+	/// Receives data into `buf`, returns the number of bytes received, `0` meaning the
+	/// connection has been closed by the other party.
+	fn receive(&mut self, buf: &mut [u8]) -> nb::Result<usize, Self::Error>;
+
+	fn is_connected(&self) -> bool;
+
+	fn close(&mut self);
+
+	/// Returns true if `error` can be recovered by opening a new connection (e.g. connection reset),
+	/// in which case idempotent requests are sent again. Errors which would happen again on a new
+	/// connection (e.g. invalid certificate) are not. All errors are recoverable by default.
+	fn is_recoverable(&self, _error: &Self::Error) -> bool {
+		true
+	}
+}
@@ -0,0 +1,74 @@
+use embedded_nal::{TcpClient, SocketAddr, nb};
+use crate::transport::Transport;
+
+#[derive(Debug)]
+pub enum TcpTransportError<E> {
+	/// No socket, the transport is not connected
+	NotConnected,
+	/// Error returned by the TCP stack
+	Stack(E),
+}
+
+/// Transport over a TCP stack implementing [`embedded_nal::TcpClient`](../../embedded_nal/trait.TcpClient.html),
+/// whatever its error type.
+pub struct TcpTransport<'a, N: TcpClient> {
+	stack: &'a N,
+	socket: Option<N::TcpSocket>,
+}
+
+impl<'a, N: TcpClient> TcpTransport<'a, N> {
+	pub fn new(stack: &'a N) -> TcpTransport<'a, N> {
+		TcpTransport {
+			stack,
+			socket: stack.socket().ok(),
+		}
+	}
+
+	fn socket(&mut self) -> nb::Result<&mut N::TcpSocket, TcpTransportError<N::Error>> {
+		self.socket.as_mut().ok_or(nb::Error::Other(TcpTransportError::NotConnected))
+	}
+}
+
+fn stack_error<E>(err: nb::Error<E>) -> nb::Error<TcpTransportError<E>> {
+	match err {
+		nb::Error::WouldBlock => nb::Error::WouldBlock,
+		nb::Error::Other(e) => nb::Error::Other(TcpTransportError::Stack(e)),
+	}
+}
+
+impl<N: TcpClient> Transport for TcpTransport<'_, N> {
+	type Error = TcpTransportError<N::Error>;
+
+	/// Connects the socket, a new socket is created if the previous one has been closed
+	fn connect(&mut self, remote: SocketAddr, _hostname: &str) -> nb::Result<(), Self::Error> {
+		if self.socket.is_none() {
+			let socket = self.stack.socket()
+				.map_err(|e| nb::Error::Other(TcpTransportError::Stack(e)))?;
+			self.socket.replace(socket);
+		}
+
+		let stack = self.stack;
+		stack.connect(self.socket()?, remote).map_err(stack_error)
+	}
+
+	fn send(&mut self, data: &[u8]) -> nb::Result<usize, Self::Error> {
+		let stack = self.stack;
+		stack.send(self.socket()?, data).map_err(stack_error)
+	}
+
+	fn receive(&mut self, buf: &mut [u8]) -> nb::Result<usize, Self::Error> {
+		let stack = self.stack;
+		stack.receive(self.socket()?, buf).map_err(stack_error)
+	}
+
+	fn is_connected(&self) -> bool {
+		self.socket.as_ref()
+			.map(|socket| self.stack.is_connected(socket))
+			.unwrap_or(Ok(false))
+			.unwrap_or(false)
+	}
+
+	fn close(&mut self) {
+		self.socket.take().and_then(|socket| self.stack.close(socket).ok());
+	}
+}
@@ -0,0 +1,138 @@
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, Shutdown};
+use std::time::Duration;
+use embedded_nal::{SocketAddr, nb};
+use crate::transport::Transport;
+
+/// Timeouts of a [`TcpStreamTransport`](struct.TcpStreamTransport.html), `None` to block indefinitely
+#[derive(Debug, Clone, Copy)]
+pub struct StreamTimeouts {
+	pub connect: Option<Duration>,
+	pub read: Option<Duration>,
+	pub write: Option<Duration>,
+}
+
+impl Default for StreamTimeouts {
+	fn default() -> StreamTimeouts {
+		StreamTimeouts {
+			connect: Some(Duration::from_secs(5)),
+			read: Some(Duration::from_secs(10)),
+			write: Some(Duration::from_secs(5)),
+		}
+	}
+}
+
+/// Transport over a blocking `std::net::TcpStream`, for desktop tools and tests. Available with the `std` feature.
+///
+/// An expired read or write timeout is reported as `WouldBlock`, so that the operation is retried
+/// until the timeout of the provider clock (see [`Provider::set_clock`](../struct.Provider.html#method.set_clock)),
+/// or returned right away without clock, as with non-blocking stacks.
+///
+/// Errors are the `io::Error` returned by the stream:
+/// * `TimedOut`: the connection cannot be opened before the connect timeout
+/// * `NotConnected`: the transport is used before `connect()`, or after `close()`
+///
+/// Reset or aborted connections are reported as errors, so that the provider reconnects.
+pub struct TcpStreamTransport {
+	stream: Option<TcpStream>,
+	timeouts: StreamTimeouts,
+}
+
+impl TcpStreamTransport {
+	/// Creates a transport with the default timeouts: 5s to connect and write, 10s to read
+	pub fn new() -> TcpStreamTransport {
+		TcpStreamTransport::with_timeouts(StreamTimeouts::default())
+	}
+
+	pub fn with_timeouts(timeouts: StreamTimeouts) -> TcpStreamTransport {
+		TcpStreamTransport {
+			stream: None,
+			timeouts,
+		}
+	}
+
+	fn stream(&mut self) -> nb::Result<&mut TcpStream, io::Error> {
+		self.stream.as_mut().ok_or_else(|| nb::Error::Other(io::Error::from(io::ErrorKind::NotConnected)))
+	}
+
+	fn open(&self, remote: std::net::SocketAddr) -> io::Result<TcpStream> {
+		let stream = match self.timeouts.connect {
+			Some(timeout) => TcpStream::connect_timeout(&remote, timeout)?,
+			None => TcpStream::connect(remote)?,
+		};
+		stream.set_read_timeout(self.timeouts.read)?;
+		stream.set_write_timeout(self.timeouts.write)?;
+		// requests are small and sent at once, don't wait to fill packets
+		stream.set_nodelay(true)?;
+
+		Ok(stream)
+	}
+}
+
+impl Default for TcpStreamTransport {
+	fn default() -> TcpStreamTransport {
+		TcpStreamTransport::new()
+	}
+}
+
+/// Converts an `embedded_nal` address into a `std` one
+fn std_addr(remote: SocketAddr) -> std::net::SocketAddr {
+	match remote {
+		SocketAddr::V4(addr) => std::net::SocketAddr::new(addr.ip().octets().into(), addr.port()),
+		SocketAddr::V6(addr) => std::net::SocketAddr::new(addr.ip().octets().into(), addr.port()),
+	}
+}
+
+/// The stream is blocking: an expired read or write timeout is reported as `WouldBlock` or
+/// `TimedOut` depending on the platform, and is always returned as `WouldBlock`.
+fn io_error(err: io::Error) -> nb::Error<io::Error> {
+	match err.kind() {
+		io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => nb::Error::WouldBlock,
+		_ => nb::Error::Other(err),
+	}
+}
+
+/// Calls `f` again while it's interrupted by a signal
+fn retry<R, F>(mut f: F) -> io::Result<R>
+	where F: FnMut() -> io::Result<R> {
+	loop {
+		match f() {
+			Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+			res => return res,
+		}
+	}
+}
+
+impl Transport for TcpStreamTransport {
+	type Error = io::Error;
+
+	fn connect(&mut self, remote: SocketAddr, _hostname: &str) -> nb::Result<(), Self::Error> {
+		self.close();
+		let stream = self.open(std_addr(remote)).map_err(nb::Error::Other)?;
+		self.stream.replace(stream);
+
+		Ok(())
+	}
+
+	fn send(&mut self, data: &[u8]) -> nb::Result<usize, Self::Error> {
+		let stream = self.stream()?;
+		retry(|| stream.write(data)).map_err(io_error)
+	}
+
+	fn receive(&mut self, buf: &mut [u8]) -> nb::Result<usize, Self::Error> {
+		let stream = self.stream()?;
+		retry(|| stream.read(buf)).map_err(io_error)
+	}
+
+	fn is_connected(&self) -> bool {
+		self.stream.as_ref()
+			.map(|stream| stream.peer_addr().is_ok())
+			.unwrap_or(false)
+	}
+
+	fn close(&mut self) {
+		if let Some(stream) = self.stream.take() {
+			let _ = stream.shutdown(Shutdown::Both);
+		}
+	}
+}
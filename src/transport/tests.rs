@@ -0,0 +1,231 @@
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::thread;
+use std::time::Duration;
+use embedded_nal::{SocketAddr, IpAddr, Ipv4Addr, nb};
+use crate::transport::{Transport, TcpStreamTransport, StreamTimeouts};
+use crate::rpc::{Endpoint, RpcError, ReconnectPolicy};
+use crate::rpc::mock::{MockClock, buffers};
+use crate::{Provider, ProviderError};
+#[cfg(feature = "tls")]
+use std::sync::Arc;
+#[cfg(feature = "tls")]
+use rustls::{Certificate, ClientConfig, NoClientAuth, PrivateKey, ServerConfig, ServerSession};
+#[cfg(feature = "tls")]
+use crate::transport::{TlsTransport, TlsError};
+#[cfg(feature = "tls")]
+use crate::rpc::Resolver;
+#[cfg(feature = "tls")]
+use crate::rpc::mock::{frame, handshake_response};
+#[cfg(feature = "tls")]
+use crate::TcpError;
+
+fn localhost(port: u16) -> SocketAddr {
+	SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), port)
+}
+
+#[test]
+fn test_tcp_stream_transport() {
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let port = listener.local_addr().unwrap().port();
+	let server = thread::spawn(move || {
+		let (mut stream, _) = listener.accept().unwrap();
+		let mut buf = [0_u8; 4];
+		stream.read_exact(&mut buf).unwrap();
+		stream.write_all(&buf).unwrap();
+	});
+
+	let mut transport = TcpStreamTransport::new();
+	match transport.send(b"ping") {
+		Err(nb::Error::Other(e)) => assert_eq!(e.kind(), std::io::ErrorKind::NotConnected),
+		_ => panic!("sent without connection"),
+	}
+
+	transport.connect(localhost(port), "127.0.0.1").unwrap();
+	assert!(transport.is_connected());
+	assert_eq!(transport.send(b"ping").unwrap(), 4);
+	let mut buf = [0_u8; 4];
+	let mut len = 0;
+	while len < buf.len() {
+		len += transport.receive(&mut buf[len..]).unwrap();
+	}
+	assert_eq!(&buf, b"ping");
+
+	server.join().unwrap();
+	// closed by the server
+	assert_eq!(transport.receive(&mut buf).unwrap(), 0);
+	transport.close();
+	assert!(!transport.is_connected());
+}
+
+#[test]
+fn test_provider_over_tcp_stream() {
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let url = format!("http://{}", listener.local_addr().unwrap());
+	let server = thread::spawn(move || {
+		let (mut stream, _) = listener.accept().unwrap();
+		// request head, then its body
+		let mut request = Vec::new();
+		let mut buf = [0_u8; 256];
+		loop {
+			let len = stream.read(&mut buf).unwrap();
+			request.extend_from_slice(&buf[..len]);
+			let text = String::from_utf8_lossy(&request);
+			if let Some(end) = text.find("\r\n\r\n") {
+				let body_len = text.lines()
+					.find_map(|line| line.strip_prefix("Content-Length: "))
+					.map(|len| len.parse::<usize>().unwrap())
+					.unwrap();
+				if request.len() >= end + 4 + body_len {
+					break
+				}
+			}
+		}
+		let body = "{\"jsonrpc\":\"2.0\",\"result\":\"leger\",\"id\":1}";
+		write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+		String::from_utf8(request).unwrap()
+	});
+
+	buffers!(buffers, 256);
+	let endpoint = Endpoint::parse(&url).unwrap();
+	let mut provider = Provider::new(TcpStreamTransport::new(), endpoint, buffers).unwrap();
+	assert_eq!(provider.system_name().unwrap(), "leger");
+
+	let request = server.join().unwrap();
+	assert!(request.starts_with("POST / HTTP/1.1\r\n"));
+	assert!(request.ends_with("\"method\":\"system_name\"}"));
+}
+
+#[test]
+fn test_tcp_stream_timeout() {
+	// connections are accepted by the system but nothing is ever sent
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let addr = listener.local_addr().unwrap();
+	let timeouts = StreamTimeouts {
+		read: Some(Duration::from_millis(20)),
+		..StreamTimeouts::default()
+	};
+
+	let mut transport = TcpStreamTransport::with_timeouts(timeouts);
+	transport.connect(localhost(addr.port()), "127.0.0.1").unwrap();
+	let mut buf = [0_u8; 4];
+	assert!(matches!(transport.receive(&mut buf), Err(nb::Error::WouldBlock)));
+	transport.close();
+
+	// the provider keeps reading until the timeout of its clock
+	let clock = MockClock::new(10);
+	let url = format!("http://{}", addr);
+	buffers!(buffers, 256);
+	let endpoint = Endpoint::parse(&url).unwrap();
+	let mut provider = Provider::new(TcpStreamTransport::with_timeouts(timeouts), endpoint, buffers).unwrap();
+	provider.set_clock(&clock, 50);
+	provider.set_reconnect_policy(ReconnectPolicy {
+		max_retries: 0,
+		..ReconnectPolicy::default()
+	});
+	assert!(matches!(provider.system_name(), Err(ProviderError::RpcError(RpcError::Timeout))));
+}
+
+/// Self-signed certificate of `localhost`, and its key
+#[cfg(feature = "tls")]
+const CERT: &[u8] = include_bytes!("certs/localhost.der");
+#[cfg(feature = "tls")]
+const KEY: &[u8] = include_bytes!("certs/localhost.key.der");
+
+#[cfg(feature = "tls")]
+#[test]
+fn test_tls_transport() {
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let port = listener.local_addr().unwrap().port();
+	let server = thread::spawn(move || {
+		let mut config = ServerConfig::new(NoClientAuth::new());
+		config.set_single_cert(vec![Certificate(CERT.to_vec())], PrivateKey(KEY.to_vec())).unwrap();
+		let mut session = ServerSession::new(&Arc::new(config));
+		let (mut stream, _) = listener.accept().unwrap();
+		let mut tls = rustls::Stream::new(&mut session, &mut stream);
+
+		let mut buf = [0_u8; 4];
+		tls.read_exact(&mut buf).unwrap();
+		assert_eq!(&buf, b"ping");
+		tls.write_all(b"pong").unwrap();
+	});
+
+	let mut config = ClientConfig::new();
+	config.root_store.add(&Certificate(CERT.to_vec())).unwrap();
+	let mut transport = TlsTransport::with_config(TcpStreamTransport::new(), Arc::new(config));
+	let remote = localhost(port);
+
+	// the certificate cannot be checked against an IP address
+	assert!(matches!(transport.connect(remote, "127.0.0.1"), Err(nb::Error::Other(TlsError::IpAddress))));
+	assert!(!transport.is_connected());
+	// and would not be on a new connection
+	assert!(!transport.is_recoverable(&TlsError::IpAddress));
+	assert!(transport.is_recoverable(&TlsError::Inner(std::io::Error::from(std::io::ErrorKind::ConnectionReset))));
+
+	transport.connect(remote, "localhost").unwrap();
+	assert_eq!(transport.send(b"ping").unwrap(), 4);
+	let mut buf = [0_u8; 4];
+	assert_eq!(transport.receive(&mut buf).unwrap(), 4);
+	assert_eq!(&buf, b"pong");
+
+	transport.close();
+	server.join().unwrap();
+}
+
+/// Resolves any host to the local host
+#[cfg(feature = "tls")]
+struct LocalResolver;
+
+#[cfg(feature = "tls")]
+impl Resolver for LocalResolver {
+	fn resolve(&self, _hostname: &str) -> Result<IpAddr, TcpError> {
+		Ok(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)))
+	}
+}
+
+#[cfg(feature = "tls")]
+#[test]
+fn test_provider_over_tls() {
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let url = format!("wss://localhost:{}", listener.local_addr().unwrap().port());
+	let server = thread::spawn(move || {
+		let mut config = ServerConfig::new(NoClientAuth::new());
+		config.set_single_cert(vec![Certificate(CERT.to_vec())], PrivateKey(KEY.to_vec())).unwrap();
+		let mut session = ServerSession::new(&Arc::new(config));
+		let (mut stream, _) = listener.accept().unwrap();
+		let mut tls = rustls::Stream::new(&mut session, &mut stream);
+
+		// websocket upgrade
+		let mut request = Vec::new();
+		let mut byte = [0_u8; 1];
+		while !request.ends_with(b"\r\n\r\n") {
+			tls.read_exact(&mut byte).unwrap();
+			request.push(byte[0]);
+		}
+		tls.write_all(&handshake_response(&request)).unwrap();
+
+		// one masked text frame, shorter than 126 bytes
+		let mut head = [0_u8; 6];
+		tls.read_exact(&mut head).unwrap();
+		let mut payload = vec![0_u8; usize::from(head[1] & 0x7f)];
+		tls.read_exact(&mut payload).unwrap();
+		for (i, byte) in payload.iter_mut().enumerate() {
+			*byte ^= head[2 + i % 4];
+		}
+		tls.write_all(&frame(true, 1, b"{\"jsonrpc\":\"2.0\",\"result\":\"leger\",\"id\":1}")).unwrap();
+		String::from_utf8(payload).unwrap()
+	});
+
+	let resolver = LocalResolver;
+	let mut config = ClientConfig::new();
+	config.root_store.add(&Certificate(CERT.to_vec())).unwrap();
+	buffers!(buffers, 512);
+	let endpoint = Endpoint::parse(&url).unwrap();
+	let transport = TlsTransport::with_config(TcpStreamTransport::new(), Arc::new(config));
+	let mut provider = Provider::new(transport, endpoint, buffers).unwrap();
+	provider.set_resolver(&resolver);
+	assert_eq!(provider.system_name().unwrap(), "leger");
+
+	let request = server.join().unwrap();
+	assert!(request.ends_with("\"method\":\"system_name\"}"));
+}
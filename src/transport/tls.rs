@@ -0,0 +1,231 @@
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+use std::net::IpAddr;
+use embedded_nal::{SocketAddr, nb};
+use rustls::{ClientConfig, ClientSession, Session, TLSError};
+use webpki::DNSNameRef;
+use crate::transport::Transport;
+
+#[derive(Debug)]
+pub enum TlsError<E> {
+	/// Error of the inner transport
+	Inner(E),
+	/// Endpoint host is not a valid DNS name
+	InvalidHostname,
+	/// Endpoint host is an IP address: the server certificate can only be checked against a DNS name
+	IpAddress,
+	/// TLS protocol error: handshake failure, invalid certificate...
+	Protocol(TLSError),
+	/// Connection closed by the server during the handshake
+	ConnectionClosed,
+	NotConnected,
+	Io(io::ErrorKind),
+}
+
+/// TLS over any other [`Transport`](trait.Transport.html) (usually a [`TcpTransport`](struct.TcpTransport.html)),
+/// to reach nodes over `wss://`. Available with the `tls` feature.
+///
+/// The server certificate is checked against the endpoint host, which must be a DNS name:
+/// endpoints given by IP address (e.g. `wss://10.0.0.2:9944`) are refused with `TlsError::IpAddress`.
+/// Use the hostname of the node instead, resolved with [`Provider::set_resolver`](../struct.Provider.html#method.set_resolver).
+pub struct TlsTransport<T> {
+	inner: T,
+	config: Arc<ClientConfig>,
+	session: Option<ClientSession>,
+	inner_connected: bool,
+}
+
+impl<T: Transport> TlsTransport<T> {
+	/// Creates a TLS transport trusting the Mozilla root certificates
+	pub fn new(inner: T) -> TlsTransport<T> {
+		let mut config = ClientConfig::new();
+		config.root_store.add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+
+		TlsTransport::with_config(inner, Arc::new(config))
+	}
+
+	/// Creates a TLS transport with a custom configuration (e.g. to trust a local certificate)
+	pub fn with_config(inner: T, config: Arc<ClientConfig>) -> TlsTransport<T> {
+		TlsTransport {
+			inner,
+			config,
+			session: None,
+			inner_connected: false,
+		}
+	}
+
+	fn session(&mut self) -> nb::Result<&mut ClientSession, TlsError<T::Error>> {
+		self.session.as_mut().ok_or(nb::Error::Other(TlsError::NotConnected))
+	}
+
+	/// Writes pending TLS records to the inner transport
+	fn flush(&mut self) -> nb::Result<(), TlsError<T::Error>> {
+		if let Some(session) = self.session.as_mut() {
+			while session.wants_write() {
+				let mut io = InnerIo { inner: &mut self.inner, error: None };
+				let res = session.write_tls(&mut io);
+				res.map_err(|e| io.nb_error(e))?;
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Reads TLS records from the inner transport, returns `false` if the connection has been closed
+	fn read_records(&mut self) -> nb::Result<bool, TlsError<T::Error>> {
+		let session = self.session.as_mut().ok_or(nb::Error::Other(TlsError::NotConnected))?;
+
+		let mut io = InnerIo { inner: &mut self.inner, error: None };
+		let res = session.read_tls(&mut io);
+		if res.map_err(|e| io.nb_error(e))? == 0 {
+			return Ok(false)
+		}
+		session.process_new_packets().map_err(|e| nb::Error::Other(TlsError::Protocol(e)))?;
+
+		Ok(true)
+	}
+}
+
+impl<T: Transport> Transport for TlsTransport<T> {
+	type Error = TlsError<T::Error>;
+
+	/// Connects the inner transport and performs the TLS handshake.
+	/// Can be called again after returning `WouldBlock` to continue the handshake.
+	fn connect(&mut self, remote: SocketAddr, hostname: &str) -> nb::Result<(), Self::Error> {
+		if !self.inner_connected {
+			let dns_name = DNSNameRef::try_from_ascii_str(hostname)
+				.map_err(|_| nb::Error::Other(hostname_error(hostname)))?;
+
+			self.inner.connect(remote, hostname).map_err(inner_error)?;
+			self.inner_connected = true;
+			self.session.replace(ClientSession::new(&self.config, dns_name));
+		}
+
+		while self.session()?.is_handshaking() {
+			self.flush()?;
+			if !self.read_records()? {
+				return Err(nb::Error::Other(TlsError::ConnectionClosed))
+			}
+		}
+
+		self.flush()
+	}
+
+	/// Data is always accepted by the TLS session, the encrypted records are sent to the inner
+	/// transport as long as it doesn't block, pending ones being sent on the next operation.
+	fn send(&mut self, data: &[u8]) -> nb::Result<usize, Self::Error> {
+		let written = self.session()?.write(data).map_err(io_error)?;
+
+		match self.flush() {
+			Ok(_) | Err(nb::Error::WouldBlock) => Ok(written),
+			Err(e) => Err(e),
+		}
+	}
+
+	fn receive(&mut self, buf: &mut [u8]) -> nb::Result<usize, Self::Error> {
+		self.flush()?;
+
+		loop {
+			let read = self.session()?.read(buf).map_err(io_error)?;
+			if read > 0 {
+				return Ok(read)
+			}
+
+			// no plaintext available, read more records
+			if !self.read_records()? {
+				return Ok(0)
+			}
+		}
+	}
+
+	fn is_connected(&self) -> bool {
+		self.session.is_some() && self.inner.is_connected()
+	}
+
+	/// Invalid hosts and TLS protocol errors (e.g. invalid certificate) are not recoverable
+	fn is_recoverable(&self, error: &Self::Error) -> bool {
+		match error {
+			TlsError::Inner(e) => self.inner.is_recoverable(e),
+			TlsError::InvalidHostname | TlsError::IpAddress | TlsError::Protocol(_) => false,
+			TlsError::ConnectionClosed | TlsError::NotConnected | TlsError::Io(_) => true,
+		}
+	}
+
+	fn close(&mut self) {
+		if let Some(mut session) = self.session.take() {
+			session.send_close_notify();
+			let _ = session.write_tls(&mut InnerIo { inner: &mut self.inner, error: None });
+		}
+
+		self.inner.close();
+		self.inner_connected = false;
+	}
+}
+
+/// Gives access to the inner transport to rustls, which reads and writes TLS records.
+/// The inner transport error is kept as it cannot be carried by `io::Error` in a generic way.
+struct InnerIo<'t, T: Transport> {
+	inner: &'t mut T,
+	error: Option<T::Error>,
+}
+
+impl<T: Transport> InnerIo<'_, T> {
+	fn io_error(&mut self, err: nb::Error<T::Error>) -> io::Error {
+		match err {
+			nb::Error::WouldBlock => io::Error::from(io::ErrorKind::WouldBlock),
+			nb::Error::Other(e) => {
+				self.error.replace(e);
+				io::Error::from(io::ErrorKind::Other)
+			}
+		}
+	}
+
+	/// Returns the inner transport error which caused `err`, if any
+	fn nb_error(self, err: io::Error) -> nb::Error<TlsError<T::Error>> {
+		match self.error {
+			Some(e) => nb::Error::Other(TlsError::Inner(e)),
+			None => io_error(err),
+		}
+	}
+}
+
+impl<T: Transport> Read for InnerIo<'_, T> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		let res = self.inner.receive(buf);
+		res.map_err(|e| self.io_error(e))
+	}
+}
+
+impl<T: Transport> Write for InnerIo<'_, T> {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		let res = self.inner.send(buf);
+		res.map_err(|e| self.io_error(e))
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+/// Error for a `hostname` which is not a valid DNS name
+fn hostname_error<E>(hostname: &str) -> TlsError<E> {
+	if hostname.parse::<IpAddr>().is_ok() {
+		TlsError::IpAddress
+	} else {
+		TlsError::InvalidHostname
+	}
+}
+
+fn inner_error<E>(err: nb::Error<E>) -> nb::Error<TlsError<E>> {
+	match err {
+		nb::Error::WouldBlock => nb::Error::WouldBlock,
+		nb::Error::Other(e) => nb::Error::Other(TlsError::Inner(e)),
+	}
+}
+
+fn io_error<E>(err: io::Error) -> nb::Error<TlsError<E>> {
+	match err.kind() {
+		io::ErrorKind::WouldBlock => nb::Error::WouldBlock,
+		kind => nb::Error::Other(TlsError::Io(kind)),
+	}
+}